@@ -0,0 +1,180 @@
+//! Source files inside archives and packages
+//!
+//! Third-party source distributions are searchable without unpacking them:
+//! zip archives (`.zip`, Java source `.jar`s, Python `.whl`s) and tarballs
+//! (`.tar`, `.tar.gz`/`.tgz` sdists, Rust `.crate`s) are read member by
+//! member. A member is indexed under a virtual path joining the archive's
+//! path and the member's path with `!/`, e.g. `vendor/serde.crate!/src/lib.rs`.
+//!
+//! Architecture Decision: The archive is the unit of change:
+//! 1. The file cache fingerprints the archive, not its members, so an
+//!    unchanged archive is never reopened.
+//! 2. Every document of a member carries the archive's path as its source
+//!    path, so a changed or deleted archive drops all of its members at once.
+//! 3. Archives nested in archives are not opened.
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::lang;
+
+/// Separates the archive path from the member path in a virtual path
+pub const MEMBER_SEPARATOR: &str = "!/";
+
+/// Container formats that can be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// File name suffixes of each format (matched case-insensitively)
+const SUFFIXES: &[(&str, ArchiveFormat)] = &[
+    (".zip", ArchiveFormat::Zip),
+    (".jar", ArchiveFormat::Zip),
+    (".whl", ArchiveFormat::Zip),
+    (".tar", ArchiveFormat::Tar),
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".crate", ArchiveFormat::TarGz),
+];
+
+/// Archive format of a file, judged by its name
+pub fn format_of(path: &Path) -> Option<ArchiveFormat> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, format)| *format)
+}
+
+/// Whether a file is an archive whose members are indexed
+pub fn is_archive(path: &Path) -> bool {
+    format_of(path).is_some()
+}
+
+/// Virtual path of an archive member
+pub fn member_path(archive_path: &str, member: &str) -> String {
+    format!("{}{}{}", archive_path, MEMBER_SEPARATOR, member)
+}
+
+/// Split a virtual path into the archive path and the member path
+pub fn split_member_path(path: &str) -> Option<(&str, &str)> {
+    path.split_once(MEMBER_SEPARATOR)
+}
+
+/// Path of the file on disk a (possibly virtual) path comes from
+pub fn source_path(path: &str) -> &str {
+    split_member_path(path).map_or(path, |(archive, _)| archive)
+}
+
+/// Call `visit` with the path, language and contents of every member in a
+/// supported language
+///
+/// At most `limit + 1` bytes of a member are read, so oversized members are
+/// still recognised as too large without being loaded.
+pub fn for_each_member<F>(path: &Path, limit: u64, mut visit: F) -> Result<()>
+where
+    F: FnMut(&str, &str, Vec<u8>) -> Result<()>,
+{
+    let format = format_of(path).context("Not an archive")?;
+    let file = File::open(path).with_context(|| format!("Failed to open archive {:?}", path))?;
+    let reader = BufReader::new(file);
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(reader)
+                .with_context(|| format!("Failed to read zip archive {:?}", path))?;
+            for i in 0..archive.len() {
+                let member = archive.by_index(i)?;
+                if !member.is_file() {
+                    continue;
+                }
+                let name = member.name().to_string();
+                let Some(language) = member_language(&name) else {
+                    continue;
+                };
+                visit(&name, language, read_limited(member, limit)?)?;
+            }
+        }
+        ArchiveFormat::Tar => visit_tar(tar::Archive::new(reader), limit, visit)?,
+        ArchiveFormat::TarGz => visit_tar(tar::Archive::new(GzDecoder::new(reader)), limit, visit)?,
+    }
+    Ok(())
+}
+
+fn visit_tar<R, F>(mut archive: tar::Archive<R>, limit: u64, mut visit: F) -> Result<()>
+where
+    R: Read,
+    F: FnMut(&str, &str, Vec<u8>) -> Result<()>,
+{
+    for entry in archive.entries().context("Failed to read tar archive")? {
+        let entry = entry.context("Failed to read tar archive")?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        let Some(language) = member_language(&name) else {
+            continue;
+        };
+        visit(&name, language, read_limited(entry, limit)?)?;
+    }
+    Ok(())
+}
+
+/// Language of a member, or None when it is not indexed (nested archives are left closed)
+fn member_language(name: &str) -> Option<&'static str> {
+    let path = Path::new(name);
+    if is_archive(path) {
+        return None;
+    }
+    lang::registry().detect(path)
+}
+
+fn read_limited(reader: impl Read, limit: u64) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_formats() {
+        let format = |name: &str| format_of(Path::new(name));
+        assert_eq!(
+            format("dist/app-1.0-py3-none-any.whl"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            format("lib/guava-33.0-sources.JAR"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            format("vendor/serde-1.0.200.crate"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(format("dist/app-1.0.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("backup.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(format("src/gzip.rs"), None);
+    }
+
+    #[test]
+    fn test_member_paths() {
+        let path = member_path("vendor/serde.crate", "serde-1.0.0/src/lib.rs");
+        assert_eq!(path, "vendor/serde.crate!/serde-1.0.0/src/lib.rs");
+        assert_eq!(
+            split_member_path(&path),
+            Some(("vendor/serde.crate", "serde-1.0.0/src/lib.rs"))
+        );
+        assert_eq!(split_member_path("src/lib.rs"), None);
+        assert_eq!(source_path(&path), "vendor/serde.crate");
+        assert_eq!(source_path("src/lib.rs"), "src/lib.rs");
+    }
+}
@@ -1,20 +1,86 @@
-//! File modification cache for incremental indexing
+//! File fingerprint cache for incremental indexing
 //!
-//! Tracks file modification timestamps to determine which files
-//! need to be re-indexed. Stores timestamps in a JSON file.
+//! Tracks a fingerprint (size, nanosecond mtime and xxh3 content hash) per
+//! file to determine which files need to be re-indexed. Size and mtime act as
+//! a cheap pre-check; the content hash decides whether the bytes really
+//! changed, so `touch` or a branch checkout that rewrites identical files
+//! does not trigger a reindex. Stored as a JSON file with a format version
+//! inside the index directory, so a rebuilt index and its cache are swapped in
+//! together; a cache in another format is dropped, which re-checks every file.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
+use xxhash_rust::xxh3::xxh3_64;
 
-/// Cache of file modification times
+use crate::lock::write_atomic;
+
+/// Format version of `file_cache.json`; bump whenever `FileFingerprint` changes
+const CACHE_VERSION: u32 = 1;
+
+/// Snapshot of a file's metadata and contents when it was last indexed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    /// File size in bytes
+    pub size: u64,
+    /// Last modified time (nanoseconds since epoch)
+    pub mtime_ns: u64,
+    /// xxh3 hash of the file contents
+    pub hash: u64,
+}
+
+impl FileFingerprint {
+    /// Fingerprint a file from contents read after `stamp` was taken
+    ///
+    /// None if the file changed since: a hash of older contents stored next
+    /// to a newer size and mtime would pass the pre-check on every later run.
+    pub fn from_content(path: &Path, stamp: FileStamp, content: &[u8]) -> Option<Self> {
+        if FileStamp::of(path)? != stamp {
+            return None;
+        }
+        Some(Self {
+            size: stamp.size,
+            mtime_ns: stamp.mtime_ns,
+            hash: xxh3_64(content),
+        })
+    }
+
+    /// Fingerprint a file by reading it, as long as it has not changed
+    /// since `stamp` was taken
+    pub fn read(path: &Path, stamp: FileStamp) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        Self::from_content(path, stamp, &bytes)
+    }
+}
+
+/// A file's size and mtime, taken before its contents are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    size: u64,
+    mtime_ns: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            mtime_ns: mtime_ns(&metadata).unwrap_or(0),
+        })
+    }
+}
+
+/// Cache of file fingerprints
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct FileCache {
-    /// Map of file path -> last modified timestamp (as seconds since epoch)
-    files: HashMap<String, u64>,
+    /// Format version (0 = written before versioning)
+    #[serde(default)]
+    version: u32,
+    /// Map of file path -> fingerprint at last index time
+    files: HashMap<String, FileFingerprint>,
     /// Path to the cache file
     #[serde(skip)]
     cache_path: PathBuf,
@@ -25,71 +91,121 @@ pub struct FileCache {
 pub enum FileStatus {
     New,
     Modified,
+    /// Metadata changed but the contents are identical; holds the
+    /// refreshed fingerprint to record
+    Touched(FileFingerprint),
     Unchanged,
 }
 
 impl FileCache {
+    /// Name of the cache file, kept inside the index directory it describes
+    pub const FILE_NAME: &'static str = "file_cache.json";
+
     /// Load cache from disk or create new
     pub fn load(cache_dir: &Path) -> Result<Self> {
-        let cache_path = cache_dir.join("file_cache.json");
+        let cache_path = cache_dir.join(Self::FILE_NAME);
 
         if cache_path.exists() {
             let content = fs::read_to_string(&cache_path)
                 .context("Failed to read file cache")?;
-            let mut cache: FileCache = serde_json::from_str(&content)
-                .context("Failed to parse file cache")?;
+            // An unreadable or outdated cache only costs a full re-check
+            let mut cache: FileCache = match serde_json::from_str::<FileCache>(&content) {
+                Ok(cache) if cache.version == CACHE_VERSION => cache,
+                Ok(cache) => {
+                    tracing::info!(
+                        "File cache format changed (v{} → v{}), re-checking every file",
+                        cache.version,
+                        CACHE_VERSION
+                    );
+                    FileCache::empty()
+                }
+                Err(e) => {
+                    tracing::warn!("Ignoring unreadable file cache: {}", e);
+                    FileCache::empty()
+                }
+            };
             cache.cache_path = cache_path;
             Ok(cache)
         } else {
             Ok(Self {
-                files: HashMap::new(),
                 cache_path,
+                ..Self::empty()
             })
         }
     }
 
+    /// A cache with no files, in the current format
+    fn empty() -> Self {
+        Self {
+            version: CACHE_VERSION,
+            ..Self::default()
+        }
+    }
+
     /// Check if a file needs to be re-indexed
     pub fn check_file(&self, path: &Path) -> FileStatus {
         let path_str = path.to_string_lossy().to_string();
 
-        let current_mtime = match fs::metadata(path)
-            .and_then(|m| m.modified())
-            .ok()
-            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-        {
-            Some(t) => t,
-            None => return FileStatus::New,
+        let Some(cached) = self.files.get(&path_str) else {
+            return FileStatus::New;
         };
 
-        match self.files.get(&path_str) {
-            None => FileStatus::New,
-            Some(&cached_mtime) => {
-                if current_mtime > cached_mtime {
-                    FileStatus::Modified
-                } else {
-                    FileStatus::Unchanged
-                }
-            }
+        let Some(stamp) = FileStamp::of(path) else {
+            return FileStatus::New;
+        };
+
+        // Cheap pre-check: same size and mtime means same contents
+        if stamp.size == cached.size && stamp.mtime_ns == cached.mtime_ns {
+            return FileStatus::Unchanged;
+        }
+        if stamp.size != cached.size {
+            return FileStatus::Modified;
+        }
+
+        // The stamp predates the read, so a later write still fails the pre-check
+        match fs::read(path) {
+            Ok(bytes) if xxh3_64(&bytes) == cached.hash => FileStatus::Touched(FileFingerprint {
+                size: stamp.size,
+                mtime_ns: stamp.mtime_ns,
+                hash: cached.hash,
+            }),
+            _ => FileStatus::Modified,
         }
     }
 
-    /// Update cache with file's current modification time
-    pub fn update_file(&mut self, path: &Path) {
-        let path_str = path.to_string_lossy().to_string();
+    /// Paths of all cached files
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
 
-        if let Ok(metadata) = fs::metadata(path) {
-            if let Ok(modified) = metadata.modified() {
-                if let Ok(duration) = modified.duration_since(SystemTime::UNIX_EPOCH) {
-                    self.files.insert(path_str, duration.as_secs());
-                }
-            }
-        }
+    /// Record a file's fingerprint as of this index run
+    ///
+    /// Fingerprints are computed on worker threads (`FileFingerprint::from_content`)
+    /// and recorded afterwards, so checking the cache never needs a lock.
+    pub fn record(&mut self, path: &Path, fingerprint: FileFingerprint) {
+        self.files
+            .insert(path.to_string_lossy().to_string(), fingerprint);
     }
 
-    /// Clear the entire cache
-    pub fn clear(&mut self) {
-        self.files.clear();
+    /// Drop a file's fingerprint so it is treated as new next time
+    pub fn forget(&mut self, path: &Path) {
+        self.files.remove(path.to_string_lossy().as_ref());
+    }
+
+    /// Drop entries for files that no longer exist, returning their paths
+    pub fn remove_missing(&mut self, seen: &HashSet<String>) -> Vec<String> {
+        let missing: Vec<String> = self
+            .files
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+
+        for path in &missing {
+            self.files.remove(path);
+        }
+
+        missing
     }
 
     /// Save cache to disk
@@ -98,7 +214,15 @@ impl FileCache {
             fs::create_dir_all(parent)?;
         }
         let content = serde_json::to_string_pretty(&self)?;
-        fs::write(&self.cache_path, content)?;
-        Ok(())
+        write_atomic(&self.cache_path, content)
     }
 }
+
+/// Modification time in nanoseconds since epoch
+fn mtime_ns(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+}
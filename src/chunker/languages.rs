@@ -1,6 +1,6 @@
 //! Language detection and tree-sitter language bindings
 //!
-//! Maps file extensions to languages and provides tree-sitter parsers
+//! Maps registry languages to tree-sitter parsers
 
 use std::path::Path;
 use tree_sitter::Language as TSLanguage;
@@ -16,14 +16,22 @@ pub enum Language {
 }
 
 impl Language {
-    /// Detect language from file path extension
+    /// Detect language from a file path via the language registry
     pub fn from_path(path: &Path) -> Self {
-        match path.extension().and_then(|e| e.to_str()) {
-            Some("rs") => Language::Rust,
-            Some("py") => Language::Python,
-            Some("js" | "jsx" | "mjs" | "cjs") => Language::JavaScript,
-            Some("ts" | "tsx") => Language::TypeScript,
-            Some("go") => Language::Go,
+        crate::lang::registry()
+            .detect(path)
+            .map(Self::from_name)
+            .unwrap_or(Language::Unknown)
+    }
+
+    /// Parse a registry language name (anything without a grammar is `Unknown`)
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "rust" => Language::Rust,
+            "python" => Language::Python,
+            "javascript" => Language::JavaScript,
+            "typescript" => Language::TypeScript,
+            "go" => Language::Go,
             _ => Language::Unknown,
         }
     }
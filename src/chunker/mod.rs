@@ -38,6 +38,9 @@ pub enum ChunkType {
     Class,
     Method,
     Struct,
+    Enum,
+    Trait,
+    Interface,
     Impl,
     Module,
     Block, // Fallback for sliding window
@@ -50,6 +53,9 @@ impl std::fmt::Display for ChunkType {
             ChunkType::Class => write!(f, "class"),
             ChunkType::Method => write!(f, "method"),
             ChunkType::Struct => write!(f, "struct"),
+            ChunkType::Enum => write!(f, "enum"),
+            ChunkType::Trait => write!(f, "trait"),
+            ChunkType::Interface => write!(f, "interface"),
             ChunkType::Impl => write!(f, "impl"),
             ChunkType::Module => write!(f, "module"),
             ChunkType::Block => write!(f, "block"),
@@ -57,6 +63,19 @@ impl std::fmt::Display for ChunkType {
     }
 }
 
+/// A symbol definition (function, type, method...) found in a file
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: ChunkType,
+    /// Starting line (1-indexed)
+    pub start_line: usize,
+    /// Ending line (1-indexed)
+    pub end_line: usize,
+    /// Enclosing type, impl, class or module (e.g. `Indexer` for `Indexer::search`)
+    pub container: Option<String>,
+}
+
 /// Main chunker that processes files into semantic units
 pub struct Chunker {
     /// Maximum chunk size in bytes (for sliding window fallback)
@@ -77,9 +96,14 @@ impl Default for Chunker {
 impl Chunker {
 
     /// Chunk a file into semantic units
-    pub fn chunk_file(&self, file_path: &Path, content: &str) -> Result<Vec<CodeChunk>> {
-        let language = Language::from_path(file_path);
-
+    ///
+    /// The language is passed in because a path doesn't always tell it (e.g. notebook cells).
+    pub fn chunk_file(
+        &self,
+        file_path: &Path,
+        content: &str,
+        language: Language,
+    ) -> Result<Vec<CodeChunk>> {
         match language {
             Language::Unknown => self.chunk_sliding_window(file_path, content, language),
             _ => {
@@ -96,6 +120,78 @@ impl Chunker {
         }
     }
 
+    /// Extract named symbol definitions from a file
+    ///
+    /// Unlike chunks there is no minimum size, so one-line functions and
+    /// unit structs are included. Files without a tree-sitter grammar have no symbols.
+    pub fn extract_symbols(&self, content: &str, language: Language) -> Vec<Symbol> {
+        let Some(ts_language) = language.tree_sitter_language() else {
+            return Vec::new();
+        };
+
+        let mut parser = tree_sitter::Parser::new();
+        if parser.set_language(&ts_language).is_err() {
+            return Vec::new();
+        }
+        let Some(tree) = parser.parse(content, None) else {
+            return Vec::new();
+        };
+
+        let mut symbols = Vec::new();
+        self.extract_symbols_recursive(tree.root_node(), content, language, None, &mut symbols);
+        symbols
+    }
+
+    /// Recursively collect symbols, tracking the nearest enclosing named definition
+    fn extract_symbols_recursive(
+        &self,
+        node: tree_sitter::Node,
+        content: &str,
+        language: Language,
+        container: Option<&(String, ChunkType)>,
+        symbols: &mut Vec<Symbol>,
+    ) {
+        let mut scope = None;
+
+        if let Some(mut kind) = self.node_to_chunk_type(node.kind(), language) {
+            // Functions defined on a type are methods
+            if kind == ChunkType::Function
+                && matches!(
+                    container,
+                    Some((_, ChunkType::Impl | ChunkType::Trait | ChunkType::Class))
+                )
+            {
+                kind = ChunkType::Method;
+            }
+
+            if let Some(name) = self.extract_name(node, content, language) {
+                // Go methods are declared outside their type; the receiver is the container
+                let enclosing = match node.kind() {
+                    "method_declaration" => go_receiver_type(node, content),
+                    _ => container.map(|(name, _)| name.clone()),
+                };
+
+                // An impl block is a scope, not a definition of its own
+                if kind != ChunkType::Impl {
+                    symbols.push(Symbol {
+                        name: name.clone(),
+                        kind,
+                        start_line: node.start_position().row + 1,
+                        end_line: node.end_position().row + 1,
+                        container: enclosing,
+                    });
+                }
+                scope = Some((name, kind));
+            }
+        }
+
+        let container = scope.as_ref().or(container);
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.extract_symbols_recursive(child, content, language, container, symbols);
+        }
+    }
+
     /// Parse with tree-sitter and extract semantic chunks
     fn chunk_with_tree_sitter(
         &self,
@@ -175,6 +271,8 @@ impl Chunker {
                 "function_item" => Some(ChunkType::Function),
                 "impl_item" => Some(ChunkType::Impl),
                 "struct_item" => Some(ChunkType::Struct),
+                "enum_item" => Some(ChunkType::Enum),
+                "trait_item" => Some(ChunkType::Trait),
                 "mod_item" => Some(ChunkType::Module),
                 _ => None,
             },
@@ -187,10 +285,13 @@ impl Chunker {
                 "function_declaration" | "arrow_function" | "function" => Some(ChunkType::Function),
                 "class_declaration" => Some(ChunkType::Class),
                 "method_definition" => Some(ChunkType::Method),
+                "interface_declaration" => Some(ChunkType::Interface),
+                "enum_declaration" => Some(ChunkType::Enum),
                 _ => None,
             },
             Language::Go => match kind {
-                "function_declaration" | "method_declaration" => Some(ChunkType::Function),
+                "function_declaration" => Some(ChunkType::Function),
+                "method_declaration" => Some(ChunkType::Method),
                 "type_declaration" => Some(ChunkType::Struct),
                 _ => None,
             },
@@ -214,8 +315,33 @@ impl Chunker {
             Language::Unknown => return None,
         };
 
-        node.child_by_field_name(name_field)
-            .map(|n| content[n.start_byte()..n.end_byte()].to_string())
+        let text = |n: tree_sitter::Node| content[n.start_byte()..n.end_byte()].to_string();
+
+        match (language, node.kind()) {
+            // `impl<T> Display for Foo<T>` is named after its type, without generics
+            (Language::Rust, "impl_item") => node.child_by_field_name("type").map(|n| {
+                let name = text(n);
+                name.split('<').next().unwrap_or_default().trim().to_string()
+            }),
+            // `const handler = () => {}` takes the variable's name
+            (Language::JavaScript | Language::TypeScript, "arrow_function" | "function") => node
+                .child_by_field_name(name_field)
+                .or_else(|| {
+                    node.parent()
+                        .filter(|p| p.kind() == "variable_declarator")
+                        .and_then(|p| p.child_by_field_name("name"))
+                })
+                .map(text),
+            // `type Foo struct {...}` names the inner type_spec
+            (Language::Go, "type_declaration") => {
+                let mut cursor = node.walk();
+                let spec = node
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() == "type_spec");
+                spec.and_then(|s| s.child_by_field_name("name")).map(text)
+            }
+            _ => node.child_by_field_name(name_field).map(text),
+        }
     }
 
     /// Fallback: chunk using sliding window with overlap
@@ -240,7 +366,7 @@ impl Chunker {
         let mut chunk_num = 0;
 
         while start < content.len() {
-            let end = (start + self.max_chunk_size).min(content.len());
+            let end = floor_char_boundary(content, start + self.max_chunk_size);
 
             // Find the start and end lines
             let start_line = content[..start].matches('\n').count() + 1;
@@ -257,10 +383,10 @@ impl Chunker {
             });
 
             chunk_num += 1;
-            start += step;
+            start = floor_char_boundary(content, start + step);
 
             // Avoid tiny trailing chunks
-            if content.len() - start < self.max_chunk_size / 4 {
+            if content.len().saturating_sub(start) < self.max_chunk_size / 4 {
                 break;
             }
         }
@@ -268,3 +394,26 @@ impl Chunker {
         Ok(chunks)
     }
 }
+
+/// Type name of a Go method receiver (`func (s *Server) Run()` → `Server`)
+fn go_receiver_type(node: tree_sitter::Node, content: &str) -> Option<String> {
+    let receiver = node.child_by_field_name("receiver")?;
+    let text = &content[receiver.start_byte()..receiver.end_byte()];
+    let name = text
+        .trim_matches(['(', ')'])
+        .split_whitespace()
+        .last()?
+        .trim_start_matches('*');
+    let name = name.split('[').next().unwrap_or(name);
+
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Largest char boundary at or before `index` (slicing mid-character panics)
+fn floor_char_boundary(content: &str, index: usize) -> usize {
+    let mut index = index.min(content.len());
+    while !content.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
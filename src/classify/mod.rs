@@ -0,0 +1,304 @@
+//! Decide whether a source file is worth indexing
+//!
+//! Extensions alone let minified bundles, generated protobuf code, lockfiles
+//! and huge JSON fixtures into the index, where they bloat it and dominate
+//! results. Before a file is indexed it is checked, cheapest test first:
+//! 1. Size against `max_file_size` (metadata only)
+//! 2. Lockfiles and paths marked `linguist-generated` in `.gitattributes`
+//! 3. Binary content, i.e. bytes that decode in no text encoding (see `encoding`)
+//! 4. Generated-code markers in the file header
+//! 5. Minified content (very long lines, in files of a few KiB or more)
+
+use anyhow::Result;
+use encoding_rs::{Encoding, WINDOWS_1252};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::encoding::{self, SourceText};
+
+/// How many leading lines may carry a generated-code marker
+const GENERATED_HEADER_LINES: usize = 5;
+/// Lines this long are minified or machine-written content
+const MINIFIED_MAX_LINE_LEN: usize = 1000;
+/// A file counts as minified when more than 1 in this many lines are that long
+/// (one long string literal or data URI doesn't make a hand-written file minified)
+const MINIFIED_LONG_LINE_RATIO: usize = 10;
+/// Average line length above which a file counts as minified
+const MINIFIED_AVG_LINE_LEN: usize = 200;
+/// Files smaller than this are never minified bundles (a one-line wrapper or
+/// a long `__all__` list is hand-written)
+const MINIFIED_MIN_SIZE: usize = 4 * 1024;
+
+/// Lockfiles are generated and never useful search results
+const LOCKFILES: &[&str] = &[
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "Cargo.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+];
+
+/// Why a file was not indexed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SkipReason {
+    TooLarge,
+    Binary,
+    Generated,
+    Minified,
+    Unreadable,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::TooLarge => write!(f, "too large"),
+            SkipReason::Binary => write!(f, "binary"),
+            SkipReason::Generated => write!(f, "generated"),
+            SkipReason::Minified => write!(f, "minified"),
+            SkipReason::Unreadable => write!(f, "unreadable"),
+        }
+    }
+}
+
+/// Classifies the files of one workspace
+#[derive(Debug)]
+pub struct FileClassifier {
+    max_file_size: u64,
+    /// Paths marked `linguist-generated` in the root `.gitattributes`
+    generated: GlobSet,
+    /// Paths explicitly marked `-linguist-generated` (override the above)
+    not_generated: GlobSet,
+    /// Index the markdown cells of notebooks too
+    notebook_markdown: bool,
+    /// Encoding of files that are neither UTF-8 nor UTF-16
+    fallback_encoding: &'static Encoding,
+}
+
+impl FileClassifier {
+    /// Build a classifier for a workspace root using the user config
+    pub fn for_root(root: &Path) -> Result<Self> {
+        let config = Config::load()?;
+        let (generated, not_generated) = load_gitattributes(root);
+
+        Ok(Self {
+            max_file_size: config.max_file_size,
+            generated,
+            not_generated,
+            notebook_markdown: config.notebook_markdown,
+            fallback_encoding: encoding::for_label(&config.fallback_encoding)
+                .unwrap_or(WINDOWS_1252),
+        })
+    }
+
+    /// Read a file if it should be indexed, or say why not
+    ///
+    /// `relative_path` is the workspace-relative path used for attribute matching.
+    pub fn read(&self, path: &Path, relative_path: &str) -> Result<SourceText, SkipReason> {
+        let metadata = fs::metadata(path).map_err(|_| SkipReason::Unreadable)?;
+        if metadata.len() > self.max_file_size {
+            return Err(SkipReason::TooLarge);
+        }
+        if self.is_generated_path(relative_path) {
+            return Err(SkipReason::Generated);
+        }
+
+        let bytes = fs::read(path).map_err(|_| SkipReason::Unreadable)?;
+        self.decode(bytes)
+    }
+
+    /// Largest file that is indexed, in bytes
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    /// Whether markdown cells of notebooks are indexed along with code cells
+    pub fn notebook_markdown(&self) -> bool {
+        self.notebook_markdown
+    }
+
+    /// Check contents that did not come from the working tree (e.g. a git blob
+    /// or an archive member)
+    pub fn check_bytes(
+        &self,
+        relative_path: &str,
+        bytes: Vec<u8>,
+    ) -> Result<SourceText, SkipReason> {
+        if bytes.len() as u64 > self.max_file_size {
+            return Err(SkipReason::TooLarge);
+        }
+        if self.is_generated_path(relative_path) {
+            return Err(SkipReason::Generated);
+        }
+        self.decode(bytes)
+    }
+
+    /// Decode text in whatever encoding it is in, then apply the content heuristics
+    fn decode(&self, bytes: Vec<u8>) -> Result<SourceText, SkipReason> {
+        let text = encoding::decode(bytes, self.fallback_encoding).ok_or(SkipReason::Binary)?;
+        classify_content(&text.content)?;
+        Ok(text)
+    }
+
+    /// Lockfiles and `.gitattributes` linguist-generated paths
+    pub fn is_generated_path(&self, relative_path: &str) -> bool {
+        let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+        if LOCKFILES.contains(&file_name) {
+            return true;
+        }
+
+        self.generated.is_match(relative_path) && !self.not_generated.is_match(relative_path)
+    }
+}
+
+/// Content heuristics: generated-code markers and minified lines
+fn classify_content(content: &str) -> Result<(), SkipReason> {
+    let header_marked = content.lines().take(GENERATED_HEADER_LINES).any(|line| {
+        line.contains("@generated")
+            || line.contains("DO NOT EDIT")
+            || line.contains("Code generated by")
+    });
+    if header_marked {
+        return Err(SkipReason::Generated);
+    }
+
+    if content.len() < MINIFIED_MIN_SIZE {
+        return Ok(());
+    }
+    let mut lines = 0;
+    let mut long_lines = 0;
+    for line in content.lines() {
+        lines += 1;
+        if line.len() > MINIFIED_MAX_LINE_LEN {
+            long_lines += 1;
+        }
+    }
+    if long_lines * MINIFIED_LONG_LINE_RATIO > lines
+        || (lines > 0 && content.len() / lines > MINIFIED_AVG_LINE_LEN)
+    {
+        return Err(SkipReason::Minified);
+    }
+
+    Ok(())
+}
+
+/// Parse `linguist-generated` patterns from the root `.gitattributes`
+///
+/// Only the root file is read. Patterns follow gitattributes rules: without
+/// a `/` they match a file name at any depth, otherwise they are anchored at
+/// the root.
+fn load_gitattributes(root: &Path) -> (GlobSet, GlobSet) {
+    let mut generated = GlobSetBuilder::new();
+    let mut not_generated = GlobSetBuilder::new();
+
+    let content = fs::read_to_string(root.join(".gitattributes")).unwrap_or_default();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let Some(pattern) = parts.next() else {
+            continue;
+        };
+
+        for attr in parts {
+            let target = match attr {
+                "linguist-generated" | "linguist-generated=true" => &mut generated,
+                "-linguist-generated" | "linguist-generated=false" => &mut not_generated,
+                _ => continue,
+            };
+            for glob in attribute_globs(pattern) {
+                target.add(glob);
+            }
+        }
+    }
+
+    (
+        generated.build().unwrap_or_else(|_| GlobSet::empty()),
+        not_generated.build().unwrap_or_else(|_| GlobSet::empty()),
+    )
+}
+
+/// Translate a gitattributes pattern into globs over workspace-relative paths
+fn attribute_globs(pattern: &str) -> Vec<Glob> {
+    let anchored = pattern.trim_start_matches('/');
+    let patterns = if pattern.trim_end_matches('/').contains('/') {
+        vec![anchored.to_string(), format!("{}/**", anchored.trim_end_matches('/'))]
+    } else {
+        vec![format!("**/{}", pattern), format!("**/{}/**", pattern)]
+    };
+
+    patterns
+        .iter()
+        .filter_map(|p| GlobBuilder::new(p).literal_separator(true).build().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_marker_in_header() {
+        let proto = "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage pb\n";
+        assert_eq!(classify_content(proto), Err(SkipReason::Generated));
+
+        let late = format!("{}// @generated\n", "fn f() {}\n".repeat(10));
+        assert_eq!(classify_content(&late), Ok(()));
+    }
+
+    #[test]
+    fn test_minified_detection() {
+        let bundle = format!("!function(){{{}}}();\n", "var a=1;".repeat(1000));
+        assert_eq!(classify_content(&bundle), Err(SkipReason::Minified));
+        assert_eq!(classify_content("fn main() {\n    println!(\"hi\");\n}\n"), Ok(()));
+
+        // One embedded data URI in an otherwise hand-written file
+        let with_long_line = format!(
+            "{}const ICON: &str = \"data:image/png;base64,{}\";\n{}",
+            "fn f() {\n    let x = 1;\n}\n".repeat(10),
+            "iVBORw0KGgo".repeat(400),
+            "fn g() {\n    let y = 2;\n}\n".repeat(10),
+        );
+        assert_eq!(classify_content(&with_long_line), Ok(()));
+    }
+
+    #[test]
+    fn test_short_files_are_not_minified() {
+        let all = format!("__all__ = [{}]\n", "\"name\", ".repeat(60));
+        assert_eq!(classify_content(&all), Ok(()));
+
+        let wrapper = format!(
+            "#!/bin/sh\nexec java -jar app.jar --icon data:image/png;base64,{} \"$@\"\n",
+            "iVBORw0KGgo".repeat(100),
+        );
+        assert_eq!(classify_content(&wrapper), Ok(()));
+    }
+
+    #[test]
+    fn test_attribute_globs() {
+        let set = |pattern: &str| {
+            let mut builder = GlobSetBuilder::new();
+            for glob in attribute_globs(pattern) {
+                builder.add(glob);
+            }
+            builder.build().unwrap()
+        };
+
+        let by_name = set("*.pb.go");
+        assert!(by_name.is_match("api/v1/user.pb.go"));
+        assert!(!by_name.is_match("api/v1/user.go"));
+
+        let anchored = set("/gen/");
+        assert!(anchored.is_match("gen/client.ts"));
+        assert!(!anchored.is_match("src/gen/client.ts"));
+    }
+}
@@ -3,10 +3,16 @@
 //! Provides a beautiful command-line interface with subcommands for:
 //! - index: Build or rebuild the search index
 //! - search: Query the index
+//! - def: Jump to symbol definitions
+//! - log-search: Search commit history
 //! - watch: Monitor filesystem for changes
 //! - similar: Find semantically similar code
 //! - config: Manage settings
 //! - status: Show index health
+//! - ls-files: Show which files are indexed and why others are not
+//! - doctor: Diagnose and repair index problems
+//! - workspaces: List indexed workspaces
+//! - root: Add or remove roots of a multi-root workspace
 
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
@@ -36,6 +42,35 @@ pub enum Commands {
         /// Also build semantic embeddings index (slower but enables natural language search)
         #[arg(short, long, default_value = "false")]
         semantic: bool,
+
+        /// Also index functions/classes as separate BM25 documents (precise hybrid results)
+        #[arg(long, default_value = "false")]
+        chunks: bool,
+
+        /// Worker threads for reading and indexing files (0 = one per core)
+        #[arg(short = 'j', long, default_value = "0")]
+        threads: usize,
+
+        /// Index a git branch, tag or commit from the object database instead of the working tree
+        #[arg(long, value_name = "REF")]
+        rev: Option<String>,
+
+        /// Also index commit history (messages and diffs) for `seekr log-search`
+        #[arg(long, default_value = "false", conflicts_with = "rev")]
+        history: bool,
+
+        /// Also index the sources of resolved dependencies (Cargo registry, node_modules,
+        /// site-packages) for `seekr search --scope deps`
+        #[arg(long, default_value = "false", conflicts_with = "rev")]
+        deps: bool,
+
+        /// List every file with what indexing would do with it, without indexing (see `ls-files`)
+        #[arg(
+            long,
+            default_value = "false",
+            conflicts_with_all = ["force", "semantic", "rev", "history", "deps"]
+        )]
+        dry_run: bool,
     },
 
     /// Search the indexed codebase
@@ -60,6 +95,10 @@ pub enum Commands {
         #[arg(long, default_value = "false")]
         hybrid: bool,
 
+        /// Treat QUERY as a regular expression and show every exact match
+        #[arg(long, default_value = "false", conflicts_with_all = ["semantic", "hybrid"])]
+        regex: bool,
+
         /// Alpha weight for hybrid search (0.0 = all semantic, 1.0 = all BM25)
         #[arg(long, default_value = "0.5")]
         alpha: f32,
@@ -67,6 +106,98 @@ pub enum Commands {
         /// Output results as JSON (for tool integration)
         #[arg(long, default_value = "false")]
         json: bool,
+
+        /// Only search files in these languages (e.g. "rust,python")
+        #[arg(long, value_name = "LANG", value_delimiter = ',')]
+        lang: Vec<String>,
+
+        /// Only search paths matching this glob (e.g. "src/", "**/*_test.go")
+        #[arg(long, value_name = "GLOB")]
+        path: Vec<String>,
+
+        /// Skip paths matching this glob
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Search a git revision indexed with `seekr index --rev` (lexical only)
+        #[arg(long, value_name = "REF", conflicts_with_all = ["semantic", "hybrid"])]
+        rev: Option<String>,
+
+        /// Search the workspace, dependency sources indexed with `seekr index --deps`,
+        /// or both (lexical only for deps and all)
+        #[arg(
+            long,
+            default_value = "workspace",
+            value_parser = ["workspace", "deps", "all"],
+            conflicts_with = "rev"
+        )]
+        scope: String,
+    },
+
+    /// Find where a function, type or method is defined
+    Def {
+        /// Symbol name, optionally qualified (e.g. "search", "Indexer::search")
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Match names starting with NAME
+        #[arg(long, default_value = "false", conflicts_with = "fuzzy")]
+        prefix: bool,
+
+        /// Match names within a small edit distance of NAME (typos)
+        #[arg(long, default_value = "false")]
+        fuzzy: bool,
+
+        /// Only show this kind of symbol (function, method, struct, class, ...)
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Maximum number of definitions to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Only search files in these languages (e.g. "rust,python")
+        #[arg(long, value_name = "LANG", value_delimiter = ',')]
+        lang: Vec<String>,
+
+        /// Only search paths matching this glob
+        #[arg(long, value_name = "GLOB")]
+        path: Vec<String>,
+
+        /// Skip paths matching this glob
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Output results as JSON (for tool integration)
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+
+    /// Search commit messages and the lines added or removed by each commit
+    LogSearch {
+        /// Search query (keywords)
+        #[arg(value_name = "QUERY")]
+        query: String,
+
+        /// Only commits whose author name or email contains these words
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Only commits authored on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+
+        /// Only changes to paths matching this glob
+        #[arg(long, value_name = "GLOB")]
+        path: Vec<String>,
+
+        /// Maximum number of hunks to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+
+        /// Output results as JSON (for tool integration)
+        #[arg(long, default_value = "false")]
+        json: bool,
     },
 
     /// Watch for file changes and auto-reindex
@@ -100,5 +231,67 @@ pub enum Commands {
     },
 
     /// Show index statistics and health
-    Status,
+    Status {
+        /// Output as JSON
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+
+    /// List candidate files with their language and whether they are indexed or why not
+    LsFiles {
+        /// Workspace to list (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Also list files left out by .gitignore, .seekrignore or as hidden paths
+        #[arg(long, default_value = "false")]
+        ignored: bool,
+
+        /// Output as JSON
+        #[arg(long, default_value = "false")]
+        json: bool,
+
+        /// Worker threads for reading files (0 = one per core)
+        #[arg(short = 'j', long, default_value = "0")]
+        threads: usize,
+    },
+
+    /// Check the indexes of this workspace for problems
+    Doctor {
+        /// Repair or rebuild whatever is broken
+        #[arg(long, default_value = "false")]
+        fix: bool,
+    },
+
+    /// List all indexed workspaces
+    Workspaces,
+
+    /// Manage the roots of this workspace (e.g. shared libs or a vendored SDK)
+    Root {
+        #[command(subcommand)]
+        action: RootAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RootAction {
+    /// Add a directory to the workspace and index it
+    Add {
+        /// Directory to add
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Label shown in results as `@label/...` (defaults to the directory name)
+        #[arg(short, long)]
+        label: Option<String>,
+    },
+
+    /// Remove a root and its files from the index
+    Remove {
+        /// Label of the root
+        label: String,
+    },
+
+    /// List the roots of the workspace
+    List,
 }
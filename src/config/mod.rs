@@ -0,0 +1,182 @@
+//! User configuration stored in ~/.seekr/config.json
+//!
+//! Settings apply to every workspace. Missing keys fall back to their
+//! defaults, so the file only needs to contain what the user changed.
+//! Read and written with `seekr config <key> [value]`.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::encoding;
+use crate::lang::{self, LanguageRegistry};
+use crate::lock::write_atomic;
+use crate::workspace::Workspace;
+
+/// Default maximum size of an indexed file (1 MiB)
+const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Default encoding of files that are not valid UTF-8
+const DEFAULT_FALLBACK_ENCODING: &str = "windows-1252";
+
+/// Persistent user settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Files larger than this many bytes are not indexed
+    pub max_file_size: u64,
+    /// Language overrides: `*.ext` or exact file name -> language ("none" = don't index)
+    pub languages: BTreeMap<String, String>,
+    /// Also index the markdown cells of Jupyter notebooks
+    pub notebook_markdown: bool,
+    /// Encoding of files that have no BOM and are not valid UTF-8 (a WHATWG label)
+    pub fallback_encoding: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            languages: BTreeMap::new(),
+            notebook_markdown: false,
+            fallback_encoding: DEFAULT_FALLBACK_ENCODING.to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Keys accepted by `get` and `set`
+    pub const KEYS: &'static [&'static str] = &[
+        "max_file_size",
+        "notebook_markdown",
+        "fallback_encoding",
+        "lang.<*.ext|file name>",
+    ];
+
+    /// Load the config, using defaults when the file does not exist
+    pub fn load() -> Result<Self> {
+        let path = config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).context("Failed to read config")?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse config {}", path.display()))
+    }
+
+    /// Save the config to disk
+    pub fn save(&self) -> Result<()> {
+        let path = config_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Current value of a key, formatted for display
+    pub fn get(&self, key: &str) -> Result<String> {
+        if let Some(pattern) = language_pattern(key)? {
+            // Show the effective mapping, built-in or overridden
+            let sample = match pattern.strip_prefix("*.") {
+                Some(extension) => format!("file.{}", extension),
+                None => pattern.to_string(),
+            };
+            let registry = LanguageRegistry::with_overrides(&self.languages);
+            return Ok(registry
+                .detect(Path::new(&sample))
+                .unwrap_or(lang::NO_LANGUAGE)
+                .to_string());
+        }
+
+        match key {
+            "max_file_size" => Ok(self.max_file_size.to_string()),
+            "notebook_markdown" => Ok(self.notebook_markdown.to_string()),
+            "fallback_encoding" => Ok(self.fallback_encoding.clone()),
+            _ => bail!(unknown_key(key)),
+        }
+    }
+
+    /// Parse and set a key's value
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        if let Some(pattern) = language_pattern(key)? {
+            self.languages
+                .insert(pattern.to_string(), value.trim().to_lowercase());
+            return Ok(());
+        }
+
+        match key {
+            "max_file_size" => self.max_file_size = parse_size(value)?,
+            "notebook_markdown" => {
+                self.notebook_markdown = value.trim().parse().with_context(|| {
+                    format!("Invalid value {:?} (expected true or false)", value)
+                })?
+            }
+            "fallback_encoding" => {
+                let encoding = encoding::for_label(value).with_context(|| {
+                    format!("Unknown encoding {:?} (e.g. latin1, shift_jis, gbk)", value)
+                })?;
+                self.fallback_encoding = encoding.name().to_string();
+            }
+            _ => bail!(unknown_key(key)),
+        }
+        Ok(())
+    }
+}
+
+fn config_path() -> Result<PathBuf> {
+    Ok(Workspace::seekr_home()?.join("config.json"))
+}
+
+/// The pattern of a `lang.<pattern>` key, if it is one
+fn language_pattern(key: &str) -> Result<Option<&str>> {
+    let Some(pattern) = key.strip_prefix("lang.") else {
+        return Ok(None);
+    };
+    if !lang::is_valid_pattern(pattern) {
+        bail!(
+            "Invalid language pattern {:?}: use `*.ext` or an exact file name",
+            pattern
+        );
+    }
+    Ok(Some(pattern))
+}
+
+fn unknown_key(key: &str) -> String {
+    format!(
+        "Unknown config key {:?} (known keys: {})",
+        key,
+        Config::KEYS.join(", ")
+    )
+}
+
+/// Parse a byte size such as `500000`, `512K`, `2M` or `1G` (binary units)
+fn parse_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1024),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024 * 1024),
+        Some((i, 'g' | 'G')) => (&value[..i], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    let invalid = || format!("Invalid size {:?} (expected e.g. 500000, 512K, 2M)", value);
+    let number: u64 = digits.trim().parse().with_context(invalid)?;
+    number.checked_mul(multiplier).with_context(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("500000").unwrap(), 500_000);
+        assert_eq!(parse_size("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("2m").unwrap(), 2 * 1024 * 1024);
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("").is_err());
+        assert!(parse_size("99999999999G").is_err());
+    }
+}
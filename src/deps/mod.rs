@@ -0,0 +1,239 @@
+//! Sources of a workspace's resolved dependencies
+//!
+//! `seekr index --deps` indexes the sources of dependencies into a separate
+//! index next to the workspace's own, searched only with
+//! `seekr search --scope deps|all`, so they never crowd out workspace results.
+//! Dependencies are read where the package managers already put them;
+//! nothing is downloaded:
+//! - Cargo: registry packages in `Cargo.lock`, from `$CARGO_HOME/registry/src`
+//! - npm: `dependencies` and `devDependencies` of `package.json`, from `node_modules`
+//! - Python: the `site-packages` of `$VIRTUAL_ENV`, `.venv` or `venv`
+//!
+//! Each dependency is indexed as a root labelled `<ecosystem>:<name>`, so its
+//! files read like `@cargo:serde-1.0.200/src/de/mod.rs`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::lock::write_atomic;
+use crate::workspace::Root;
+
+/// Dependency roots last indexed, kept inside the deps index
+const INDEXED_FILE: &str = "dependencies.json";
+
+/// Resolved dependencies of the workspace's roots whose sources are on disk
+pub fn discover(roots: &[Root]) -> Result<Vec<Root>> {
+    let mut found = BTreeMap::new();
+    for root in roots {
+        for dependency in cargo_dependencies(&root.path)?
+            .into_iter()
+            .chain(npm_dependencies(&root.path)?)
+            .chain(python_dependencies(&root.path))
+        {
+            found.entry(dependency.label.clone()).or_insert(dependency);
+        }
+    }
+    Ok(found.into_values().collect())
+}
+
+/// Dependency roots recorded by the last `seekr index --deps`
+pub fn load_indexed(index_path: &Path) -> Result<Vec<Root>> {
+    let path = index_path.join(INDEXED_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let mut roots: Vec<Root> =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+    for root in &mut roots {
+        root.dependency = true;
+    }
+    Ok(roots)
+}
+
+/// Record the dependency roots now in the deps index
+pub fn save_indexed(index_path: &Path, roots: &[Root]) -> Result<()> {
+    write_atomic(
+        &index_path.join(INDEXED_FILE),
+        serde_json::to_string_pretty(roots)?,
+    )
+}
+
+/// Ecosystem of a dependency root (the part of its label before `:`)
+pub fn ecosystem(root: &Root) -> &str {
+    root.label
+        .split_once(':')
+        .map_or("", |(ecosystem, _)| ecosystem)
+}
+
+fn dependency_root(ecosystem: &str, name: &str, path: PathBuf) -> Root {
+    Root {
+        label: format!("{}:{}", ecosystem, name.replace('/', "+")),
+        path,
+        main: false,
+        dependency: true,
+    }
+}
+
+fn cargo_dependencies(root: &Path) -> Result<Vec<Root>> {
+    let lock_path = root.join("Cargo.lock");
+    if !lock_path.exists() {
+        return Ok(Vec::new());
+    }
+    let lock = fs::read_to_string(&lock_path)
+        .with_context(|| format!("Failed to read {:?}", lock_path))?;
+
+    // Unpacked crates live in one directory per registry index
+    let Some(registry_src) = cargo_home().map(|home| home.join("registry").join("src")) else {
+        return Ok(Vec::new());
+    };
+    let registries: Vec<PathBuf> = match fs::read_dir(&registry_src) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut dependencies = Vec::new();
+    for (name, version) in registry_packages(&lock) {
+        let dir_name = format!("{}-{}", name, version);
+        if let Some(path) = registries
+            .iter()
+            .map(|registry| registry.join(&dir_name))
+            .find(|path| path.is_dir())
+        {
+            dependencies.push(dependency_root("cargo", &dir_name, path));
+        }
+    }
+    Ok(dependencies)
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Name and version of every `[[package]]` in a Cargo.lock that comes from a
+/// registry (path and git dependencies are not unpacked there)
+fn registry_packages(lock: &str) -> Vec<(String, String)> {
+    let mut packages = Vec::new();
+    for block in lock.split("[[package]]").skip(1) {
+        let mut name = None;
+        let mut version = None;
+        let mut registry = false;
+        for line in block.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "version" => version = Some(value.to_string()),
+                "source" => {
+                    registry = value.starts_with("registry+") || value.starts_with("sparse+")
+                }
+                _ => {}
+            }
+        }
+        if let (Some(name), Some(version), true) = (name, version, registry) {
+            packages.push((name, version));
+        }
+    }
+    packages
+}
+
+#[derive(Deserialize)]
+struct PackageJson {
+    #[serde(default)]
+    dependencies: BTreeMap<String, serde_json::Value>,
+    #[serde(default, rename = "devDependencies")]
+    dev_dependencies: BTreeMap<String, serde_json::Value>,
+}
+
+fn npm_dependencies(root: &Path) -> Result<Vec<Root>> {
+    let manifest_path = root.join("package.json");
+    if !manifest_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {:?}", manifest_path))?;
+    let manifest: PackageJson = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {:?}", manifest_path))?;
+
+    let names: BTreeSet<&String> = manifest
+        .dependencies
+        .keys()
+        .chain(manifest.dev_dependencies.keys())
+        .collect();
+    Ok(names
+        .into_iter()
+        .map(|name| (name, root.join("node_modules").join(name)))
+        .filter(|(_, path)| path.is_dir())
+        .map(|(name, path)| dependency_root("npm", name, path))
+        .collect())
+}
+
+fn python_dependencies(root: &Path) -> Vec<Root> {
+    let mut environments: Vec<PathBuf> = vec![root.join(".venv"), root.join("venv")];
+    if let Some(active) = std::env::var_os("VIRTUAL_ENV") {
+        environments.insert(0, PathBuf::from(active));
+    }
+
+    for environment in environments {
+        if let Some(site_packages) = site_packages(&environment) {
+            return vec![dependency_root("python", "site-packages", site_packages)];
+        }
+    }
+    Vec::new()
+}
+
+/// `lib/python3.X/site-packages` (or `Lib/site-packages` on Windows) of a virtualenv
+fn site_packages(environment: &Path) -> Option<PathBuf> {
+    let windows = environment.join("Lib").join("site-packages");
+    if windows.is_dir() {
+        return Some(windows);
+    }
+    fs::read_dir(environment.join("lib"))
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("python"))
+        .map(|entry| entry.path().join("site-packages"))
+        .find(|path| path.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_packages() {
+        let lock = r#"
+version = 3
+
+[[package]]
+name = "seekr"
+version = "0.1.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "ddc6f9cc94d67c0e21aaf7eda3a010fd3af78ebf6e096aa6e2e13c79749cce4f"
+
+[[package]]
+name = "patched"
+version = "0.2.0"
+source = "git+https://github.com/example/patched#0123abcd"
+"#;
+        assert_eq!(
+            registry_packages(lock),
+            vec![("serde".to_string(), "1.0.200".to_string())]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Health checks for `seekr doctor`
+//!
+//! Each check inspects one part of a workspace's data and, when something
+//! is wrong, names the repair `seekr doctor --fix` applies. Checks never
+//! modify anything; repairs are carried out by the caller, which owns the
+//! indexing pipeline.
+
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use crate::cache::{FileCache, FileStatus};
+use crate::embedder;
+use crate::history::HISTORY_VERSION;
+use crate::indexer::{self, Indexer, SCHEMA_VERSION};
+use crate::semantic::{SemanticIndexer, SemanticSummary};
+use crate::workspace::{Workspace, WorkspaceRegistry};
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    /// Nothing wrong, but worth knowing (e.g. an optional index is not built)
+    Info,
+    Problem,
+}
+
+/// A repair for a failed check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// Rebuild the lexical index from scratch
+    RebuildIndex,
+    /// Run an incremental index to catch up with the files on disk
+    UpdateIndex,
+    RebuildSemantic,
+    RebuildHistory,
+    DownloadModel,
+    RewriteWorkspaceFile,
+    /// Unregister these workspace ids and delete their data
+    ForgetWorkspaces(Vec<String>),
+}
+
+impl std::fmt::Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Repair::RebuildIndex => write!(f, "rebuild the lexical index"),
+            Repair::UpdateIndex => write!(f, "update the index"),
+            Repair::RebuildSemantic => write!(f, "rebuild the semantic index"),
+            Repair::RebuildHistory => write!(f, "rebuild the history index"),
+            Repair::DownloadModel => write!(f, "download the embedding model"),
+            Repair::RewriteWorkspaceFile => write!(f, "rewrite workspace.txt"),
+            Repair::ForgetWorkspaces(_) => write!(f, "forget workspaces that no longer exist"),
+        }
+    }
+}
+
+/// Result of one check
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub repair: Option<Repair>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            repair: None,
+        }
+    }
+
+    fn info(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Info,
+            ..Self::ok(name, detail)
+        }
+    }
+
+    fn problem(name: &'static str, detail: impl Into<String>, repair: Repair) -> Self {
+        Self {
+            status: CheckStatus::Problem,
+            repair: Some(repair),
+            ..Self::ok(name, detail)
+        }
+    }
+}
+
+/// Run every check for a workspace
+pub fn diagnose(workspace: &Workspace) -> Result<Vec<Check>> {
+    let semantic = check_semantic(workspace);
+    let semantic_built = semantic.status != CheckStatus::Info;
+
+    Ok(vec![
+        check_lexical(workspace),
+        check_workspace_file(workspace),
+        check_file_cache(workspace)?,
+        semantic,
+        check_model(semantic_built)?,
+        check_history(workspace),
+        check_registry()?,
+    ])
+}
+
+fn check_lexical(workspace: &Workspace) -> Check {
+    const NAME: &str = "Lexical index";
+
+    let index_path = workspace.index_path();
+    if !index_path.join("meta.json").exists() {
+        return Check::problem(NAME, "missing", Repair::RebuildIndex);
+    }
+
+    let problems = indexer::verify_index(&index_path, SCHEMA_VERSION);
+    if !problems.is_empty() {
+        return Check::problem(NAME, problems.join("; "), Repair::RebuildIndex);
+    }
+    match Indexer::get_status(&index_path) {
+        Ok(status) => Check::ok(NAME, format!("{} documents", status.num_docs)),
+        Err(e) => Check::problem(NAME, format!("{:#}", e), Repair::RebuildIndex),
+    }
+}
+
+/// `workspace.txt` must name the workspace root
+fn check_workspace_file(workspace: &Workspace) -> Check {
+    const NAME: &str = "Workspace";
+
+    let root = workspace.root().to_string_lossy();
+    match fs::read_to_string(workspace.index_path().join("workspace.txt")) {
+        Ok(recorded) if recorded.trim_end() == root => Check::ok(NAME, root),
+        Ok(recorded) => Check::problem(
+            NAME,
+            format!("workspace.txt points to {}", recorded.trim_end()),
+            Repair::RewriteWorkspaceFile,
+        ),
+        Err(_) => Check::problem(
+            NAME,
+            "workspace.txt is missing",
+            Repair::RewriteWorkspaceFile,
+        ),
+    }
+}
+
+/// Cache entries must match the files on disk, or the next search sees stale results
+fn check_file_cache(workspace: &Workspace) -> Result<Check> {
+    const NAME: &str = "File cache";
+
+    let cache = FileCache::load(&workspace.index_path())?;
+    let mut tracked = 0;
+    let mut missing = 0;
+    let mut changed = 0;
+    for path in cache.paths() {
+        tracked += 1;
+        let path = Path::new(path);
+        if !path.exists() {
+            missing += 1;
+        } else if matches!(
+            cache.check_file(path),
+            FileStatus::Modified | FileStatus::New
+        ) {
+            // Touched files (new mtime, same contents) don't make results stale
+            changed += 1;
+        }
+    }
+
+    if missing == 0 && changed == 0 {
+        return Ok(Check::ok(NAME, format!("{} files tracked", tracked)));
+    }
+    Ok(Check::problem(
+        NAME,
+        format!(
+            "{} cached files no longer exist, {} changed since the last index",
+            missing, changed
+        ),
+        Repair::UpdateIndex,
+    ))
+}
+
+fn check_semantic(workspace: &Workspace) -> Check {
+    const NAME: &str = "Semantic index";
+
+    match SemanticIndexer::summary(&workspace.semantic_path()) {
+        None => Check::info(NAME, "not built (`seekr index --semantic`)"),
+        Some(SemanticSummary {
+            problem: Some(problem),
+            ..
+        }) => Check::problem(NAME, problem, Repair::RebuildSemantic),
+        Some(summary) => Check::ok(NAME, format!("{} vectors", summary.vectors)),
+    }
+}
+
+/// The model is only required once a semantic index exists
+fn check_model(semantic_built: bool) -> Result<Check> {
+    const NAME: &str = "Embedding model";
+
+    let location = embedder::model_cache_dir()?;
+    Ok(if embedder::is_model_cached()? {
+        Check::ok(
+            NAME,
+            format!("{} in {}", embedder::MODEL_NAME, location.display()),
+        )
+    } else if semantic_built {
+        Check::problem(
+            NAME,
+            format!("{} is not downloaded", embedder::MODEL_NAME),
+            Repair::DownloadModel,
+        )
+    } else {
+        Check::info(NAME, "not downloaded yet (fetched on first semantic index)")
+    })
+}
+
+fn check_history(workspace: &Workspace) -> Check {
+    const NAME: &str = "History index";
+
+    let index_path = workspace.history_index_path();
+    if !index_path.join("meta.json").exists() {
+        return Check::info(NAME, "not built (`seekr index --history`)");
+    }
+
+    let problems = indexer::verify_index(&index_path, HISTORY_VERSION);
+    if problems.is_empty() {
+        Check::ok(NAME, "readable")
+    } else {
+        Check::problem(NAME, problems.join("; "), Repair::RebuildHistory)
+    }
+}
+
+/// Registered workspaces whose root was deleted or moved
+fn check_registry() -> Result<Check> {
+    const NAME: &str = "Registry";
+
+    let registry = WorkspaceRegistry::load()?;
+    let gone: Vec<_> = registry
+        .entries()
+        .iter()
+        .filter(|entry| !entry.root.exists())
+        .collect();
+
+    if gone.is_empty() {
+        return Ok(Check::ok(
+            NAME,
+            format!("{} workspaces", registry.entries().len()),
+        ));
+    }
+    let roots: Vec<String> = gone.iter().map(|e| e.root.display().to_string()).collect();
+    Ok(Check::problem(
+        NAME,
+        format!("no longer exist: {}", roots.join(", ")),
+        Repair::ForgetWorkspaces(gone.iter().map(|e| e.id.clone()).collect()),
+    ))
+}
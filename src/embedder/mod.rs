@@ -2,9 +2,25 @@
 //!
 //! Using fastembed with BGE-small model (384 dimensions) for fast,
 //! local semantic embeddings via ONNX Runtime.
+//!
+//! The model is downloaded once into ~/.seekr/models and shared by every
+//! workspace, instead of a `.fastembed_cache` in whatever directory seekr
+//! happened to run in.
 
 use anyhow::{Context, Result};
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::workspace::Workspace;
+
+/// The embedding model
+const MODEL: EmbeddingModel = EmbeddingModel::BGESmallENV15;
+
+/// Name of the embedding model, recorded with stored vectors
+pub const MODEL_NAME: &str = "bge-small-en-v1.5";
+/// Dimension of the model's embeddings
+pub const DIMENSION: usize = 384;
 
 /// Wrapper around fastembed for generating text embeddings
 pub struct Embedder {
@@ -17,7 +33,9 @@ impl Embedder {
         tracing::info!("Loading embedding model (bge-small-en-v1.5)...");
 
         let model = TextEmbedding::try_new(
-            InitOptions::new(EmbeddingModel::BGESmallENV15).with_show_download_progress(true),
+            InitOptions::new(MODEL)
+                .with_cache_dir(model_cache_dir()?)
+                .with_show_download_progress(true),
         )
         .context("Failed to initialize embedding model")?;
 
@@ -47,6 +65,28 @@ impl Embedder {
     }
 }
 
+/// Directory holding downloaded models (~/.seekr/models)
+pub fn model_cache_dir() -> Result<PathBuf> {
+    Ok(Workspace::seekr_home()?.join("models"))
+}
+
+/// Whether the model has been downloaded (it is fetched on first use otherwise)
+pub fn is_model_cached() -> Result<bool> {
+    let info = TextEmbedding::get_model_info(&MODEL)?;
+
+    // Hugging Face hub layout: models--<org>--<name>/snapshots/<revision>/<file>
+    let snapshots = model_cache_dir()?
+        .join(format!("models--{}", info.model_code.replace('/', "--")))
+        .join("snapshots");
+    let Ok(revisions) = fs::read_dir(snapshots) else {
+        return Ok(false);
+    };
+
+    Ok(revisions
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.path().join(&info.model_file).exists()))
+}
+
 impl Default for Embedder {
     fn default() -> Self {
         Self::new().expect("Failed to create default embedder")
@@ -0,0 +1,170 @@
+//! Text encodings of source files
+//!
+//! Older code bases keep sources in legacy encodings: Latin-1 or
+//! Windows-1252 C++ and Java files, UTF-16 resource and header files from
+//! Windows tools. Such files are transcoded to UTF-8 for indexing, and the
+//! encoding is stored with the file so results can name it. Line breaks
+//! survive transcoding, so line numbers match the file on disk. Detection,
+//! most reliable first:
+//! 1. A byte order mark (UTF-8, UTF-16LE, UTF-16BE)
+//! 2. UTF-16 without a BOM, recognised by its NUL high bytes
+//! 3. Valid UTF-8, kept as is
+//! 4. The `fallback_encoding` setting (Windows-1252 by default, a superset
+//!    of Latin-1's printable characters)
+//!
+//! Content that is none of these (NUL bytes, or control characters after
+//! decoding) is binary.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+/// How many leading bytes are inspected for NUL bytes and UTF-16
+const SNIFF_LEN: usize = 8192;
+
+/// Decoded text in which more than 1 in this many characters is a control
+/// character is binary data that happens to decode
+const CONTROL_CHAR_RATIO: usize = 100;
+
+/// Contents of a source file, as UTF-8
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceText {
+    pub content: String,
+    /// Encoding the file was transcoded from; None for UTF-8 without a BOM,
+    /// whose bytes are the content itself
+    pub encoding: Option<&'static Encoding>,
+}
+
+impl SourceText {
+    /// Name of the file's encoding if it is not plain UTF-8 (e.g. "windows-1252")
+    pub fn encoding_name(&self) -> Option<&'static str> {
+        self.encoding.map(Encoding::name)
+    }
+}
+
+impl From<String> for SourceText {
+    fn from(content: String) -> Self {
+        Self {
+            content,
+            encoding: None,
+        }
+    }
+}
+
+/// Decode a file's bytes, or None if they are binary
+pub fn decode(bytes: Vec<u8>, fallback: &'static Encoding) -> Option<SourceText> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&bytes) {
+        return transcode(&bytes[bom_len..], encoding);
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if let Some(encoding) = sniff_utf16(sample) {
+        return transcode(&bytes, encoding);
+    }
+    if sample.contains(&0) {
+        return None;
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(content) => Some(content.into()),
+        Err(e) => transcode(e.as_bytes(), fallback),
+    }
+}
+
+/// Look up an encoding by any of its WHATWG labels (e.g. "latin1", "shift_jis")
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+fn transcode(bytes: &[u8], encoding: &'static Encoding) -> Option<SourceText> {
+    let content = encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)?
+        .into_owned();
+    if looks_binary(&content) {
+        return None;
+    }
+    Some(SourceText {
+        content,
+        encoding: Some(encoding),
+    })
+}
+
+/// UTF-16 without a BOM: mostly-ASCII text has a NUL in every other byte
+fn sniff_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros = |offset: usize| {
+        sample
+            .chunks_exact(2)
+            .filter(|pair| pair[offset] == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+
+    if even == 0 && odd * 10 >= pairs * 7 {
+        Some(UTF_16LE)
+    } else if odd == 0 && even * 10 >= pairs * 7 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn looks_binary(content: &str) -> bool {
+    let mut chars = 0;
+    let mut controls = 0;
+    for c in content.chars() {
+        chars += 1;
+        if c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c') {
+            controls += 1;
+        }
+    }
+    controls * CONTROL_CHAR_RATIO > chars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{UTF_8, WINDOWS_1252};
+
+    #[test]
+    fn test_detect_encodings() {
+        let decode = |bytes: &[u8]| decode(bytes.to_vec(), WINDOWS_1252).unwrap();
+
+        let plain = decode("fn naïve() {}\n".as_bytes());
+        assert_eq!(plain.encoding, None);
+
+        let bom = decode(b"\xEF\xBB\xBFint x;\n");
+        assert_eq!(
+            (bom.content.as_str(), bom.encoding),
+            ("int x;\n", Some(UTF_8))
+        );
+
+        // "// Größe\n" in Windows-1252
+        let latin = decode(b"// Gr\xF6\xDFe\n");
+        assert_eq!(latin.content, "// Größe\n");
+        assert_eq!(latin.encoding_name(), Some("windows-1252"));
+
+        let utf16: Vec<u8> = "class Ä {}\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let with_bom = [&[0xFF, 0xFE][..], &utf16].concat();
+        assert_eq!(decode(&with_bom).content, "class Ä {}\r\n");
+        let without_bom = decode(&utf16);
+        assert_eq!(without_bom.content, "class Ä {}\r\n");
+        assert_eq!(without_bom.encoding, Some(UTF_16LE));
+    }
+
+    #[test]
+    fn test_binary_content() {
+        assert_eq!(
+            decode(b"\x7FELF\x02\x01\x01\0\0\0".to_vec(), WINDOWS_1252),
+            None
+        );
+        assert_eq!(
+            decode(vec![0x81, 0x8D, 0x8F, 0x90, 0x9D], WINDOWS_1252),
+            None
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Search filters shared by lexical, semantic and hybrid search
+//!
+//! Restricts results by language (`--lang`) and by workspace-relative path
+//! globs (`--path`, `--exclude`). Lexical search pushes the language filter
+//! into the Tantivy query; path globs are applied to candidates from every
+//! search mode through `matches`.
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Language and path restrictions for a search
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+    languages: Vec<String>,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl SearchFilter {
+    /// Build a filter from CLI values (empty slices mean "no restriction")
+    pub fn new(languages: &[String], include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            languages: languages.iter().map(|l| l.to_lowercase()).collect(),
+            include: build_globs(include)?,
+            exclude: build_globs(exclude)?,
+        })
+    }
+
+    /// Languages to restrict to (lowercase), empty for all
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// Whether path globs are set (lexical search must post-filter)
+    pub fn has_path_globs(&self) -> bool {
+        self.include.is_some() || self.exclude.is_some()
+    }
+
+    /// Check a result's workspace-relative path and language against the filter
+    pub fn matches(&self, file_path: &str, language: &str) -> bool {
+        if !self.languages.is_empty() && !self.languages.iter().any(|l| l == language) {
+            return false;
+        }
+        self.matches_path(file_path)
+    }
+
+    /// Check only the path globs
+    pub fn matches_path(&self, file_path: &str) -> bool {
+        if let Some(include) = &self.include {
+            if !include.is_match(file_path) {
+                return false;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(file_path) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Compile globs; a plain directory such as `src` or `src/` also matches everything below it
+fn build_globs(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let trimmed = pattern.trim_start_matches("./").trim_end_matches('/');
+        builder.add(Glob::new(trimmed).with_context(|| format!("Invalid glob {:?}", pattern))?);
+        builder.add(Glob::new(&format!("{}/**", trimmed))?);
+    }
+
+    Ok(Some(builder.build()?))
+}
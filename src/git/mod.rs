@@ -0,0 +1,303 @@
+//! Read workspace snapshots straight from the git object database
+//!
+//! Architecture Decision: Revisions are indexed from blobs rather than by
+//! checking them out, so indexing `main` or a release tag never touches the
+//! working tree or the user's current branch. Each worker thread opens its
+//! own `Repository` handle (libgit2 handles are not shareable across threads)
+//! and reads its share of the tree's blobs.
+//!
+//! Commit history (`seekr log-search`) is read the same way: each commit is
+//! diffed against its first parent and reported as hunks of added and
+//! removed lines.
+
+use anyhow::{Context, Result};
+use git2::{
+    Commit, DiffOptions, ErrorCode, ObjectType, Oid, Patch, Repository, Sort, TreeWalkMode,
+    TreeWalkResult,
+};
+use std::path::{Path, PathBuf};
+
+use crate::walk::resolve_threads;
+
+/// A resolved git revision
+#[derive(Debug, Clone)]
+pub struct Revision {
+    /// The ref as given by the user (`main`, `v1.2.0`, `HEAD~3`)
+    pub name: String,
+    /// Full commit id
+    pub sha: String,
+}
+
+impl Revision {
+    /// Abbreviated commit id for display
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(10)]
+    }
+}
+
+/// A commit and the hunks it changed under the workspace root
+#[derive(Debug, Clone)]
+pub struct CommitDiff {
+    /// Full commit id
+    pub sha: String,
+    /// Author as "Name <email>"
+    pub author: String,
+    /// Author time (seconds since epoch)
+    pub time: i64,
+    /// Full commit message
+    pub message: String,
+    /// Hunks against the first parent (empty for merges)
+    pub hunks: Vec<Hunk>,
+}
+
+/// One hunk of a commit's diff
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// Workspace-relative path (the old path for deletions)
+    pub path: String,
+    /// Hunk header, e.g. "@@ -10,2 +10,3 @@ fn main()"
+    pub header: String,
+    /// First line of the hunk in the new file
+    pub new_start: usize,
+    /// Added and removed lines, each prefixed with `+` or `-`
+    pub changes: Vec<String>,
+}
+
+/// Resolve a branch, tag or commit-ish to a commit in the repository containing `root`
+pub fn resolve(root: &Path, name: &str) -> Result<Revision> {
+    let repo = Repository::discover(root)
+        .with_context(|| format!("{} is not inside a git repository", root.display()))?;
+    let commit = repo
+        .revparse_single(name)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Unknown git revision {:?}", name))?;
+
+    Ok(Revision {
+        name: name.to_string(),
+        sha: commit.id().to_string(),
+    })
+}
+
+/// Call `visit` with the workspace-relative path and contents of every file
+/// under `root` at `revision`, on `threads` worker threads (0 = one per core)
+///
+/// Hidden paths are skipped to match the working-tree walker. A thread
+/// stops at its first error, which is returned.
+pub fn for_each_blob<F>(root: &Path, revision: &Revision, threads: usize, visit: F) -> Result<()>
+where
+    F: Fn(&str, Vec<u8>) -> Result<()> + Sync,
+{
+    let repo = Repository::discover(root)?;
+    let repo_path = repo.path().to_path_buf();
+    let blobs = list_blobs(&repo, root, revision)?;
+
+    let per_thread = blobs.len().div_ceil(resolve_threads(threads)).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = blobs
+            .chunks(per_thread)
+            .map(|slice| {
+                let repo_path = &repo_path;
+                let visit = &visit;
+                scope.spawn(move || -> Result<()> {
+                    let repo = Repository::open(repo_path)?;
+                    for (path, oid) in slice {
+                        let blob = repo
+                            .find_blob(*oid)
+                            .with_context(|| format!("Failed to read {} from git", path))?;
+                        visit(path, blob.content().to_vec())?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().expect("git reader thread panicked"))
+    })
+}
+
+/// Call `visit` for every commit reachable from HEAD, newest first
+///
+/// Commits reachable from `since` (a previously indexed HEAD) are not
+/// walked, and commits for which `skip` returns true are not diffed. Merge
+/// commits are reported without hunks. In a workspace below the repository
+/// root, other commits that change nothing under it are not reported.
+/// Returns the HEAD commit id, or None when the repository has no commits yet.
+pub fn for_each_commit<S, F>(
+    root: &Path,
+    since: Option<&str>,
+    skip: S,
+    mut visit: F,
+) -> Result<Option<String>>
+where
+    S: Fn(&str) -> bool,
+    F: FnMut(CommitDiff) -> Result<()>,
+{
+    let repo = Repository::discover(root)
+        .with_context(|| format!("{} is not inside a git repository", root.display()))?;
+    let head = match repo.head() {
+        Ok(head) => head.peel_to_commit()?,
+        Err(e) if e.code() == ErrorCode::UnbornBranch => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let prefix = workspace_prefix(&repo, root)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TIME)?;
+    revwalk.push(head.id())?;
+    // A rewritten history may no longer contain the old HEAD
+    if let Some(oid) = since.and_then(|sha| Oid::from_str(sha).ok()) {
+        if repo.find_commit(oid).is_ok() {
+            revwalk.hide(oid)?;
+        }
+    }
+
+    for oid in revwalk {
+        let oid = oid?;
+        let sha = oid.to_string();
+        if skip(&sha) {
+            continue;
+        }
+
+        let commit = repo.find_commit(oid)?;
+        let is_merge = commit.parent_count() > 1;
+        let hunks = if is_merge {
+            Vec::new()
+        } else {
+            diff_hunks(&repo, &commit, &prefix)
+                .with_context(|| format!("Failed to diff commit {}", sha))?
+        };
+        if hunks.is_empty() && !is_merge && !prefix.as_os_str().is_empty() {
+            continue;
+        }
+        let author = commit.author();
+
+        visit(CommitDiff {
+            sha,
+            author: format!(
+                "{} <{}>",
+                author.name().unwrap_or_default(),
+                author.email().unwrap_or_default()
+            ),
+            time: author.when().seconds(),
+            message: commit.message().unwrap_or_default().trim_end().to_string(),
+            hunks,
+        })?;
+    }
+
+    Ok(Some(head.id().to_string()))
+}
+
+/// Hunks of a commit against its first parent (or the empty tree), under `prefix`
+fn diff_hunks(repo: &Repository, commit: &Commit, prefix: &Path) -> Result<Vec<Hunk>> {
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    if !prefix.as_os_str().is_empty() {
+        options.pathspec(prefix);
+    }
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))?;
+
+    let mut hunks = Vec::new();
+    for delta_index in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, delta_index)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        if delta.flags().is_binary() {
+            continue;
+        }
+        let Some(path) = delta.new_file().path().or_else(|| delta.old_file().path()) else {
+            continue;
+        };
+        let Ok(path) = path.strip_prefix(prefix) else {
+            continue;
+        };
+        // Hidden paths are skipped, as in `list_blobs`
+        let path = path.to_string_lossy().replace('\\', "/");
+        if path.split('/').any(|component| component.starts_with('.')) {
+            continue;
+        }
+
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_index)?;
+            let mut changes = Vec::new();
+            for line_index in 0..line_count {
+                let line = patch.line_in_hunk(hunk_index, line_index)?;
+                if matches!(line.origin(), '+' | '-') {
+                    let content = String::from_utf8_lossy(line.content());
+                    changes.push(format!(
+                        "{}{}",
+                        line.origin(),
+                        content.trim_end_matches(['\r', '\n'])
+                    ));
+                }
+            }
+
+            hunks.push(Hunk {
+                path: path.clone(),
+                header: String::from_utf8_lossy(hunk.header())
+                    .trim_end()
+                    .to_string(),
+                new_start: hunk.new_start() as usize,
+                changes,
+            });
+        }
+    }
+
+    Ok(hunks)
+}
+
+/// List (workspace-relative path, blob id) for every file under `root` at `revision`
+fn list_blobs(repo: &Repository, root: &Path, revision: &Revision) -> Result<Vec<(String, Oid)>> {
+    let commit = repo.find_commit(Oid::from_str(&revision.sha)?)?;
+    let mut tree = commit.tree()?;
+
+    // A workspace may be a subdirectory of the repository
+    let prefix = workspace_prefix(repo, root)?;
+    if !prefix.as_os_str().is_empty() {
+        let entry = tree.get_path(&prefix).with_context(|| {
+            format!(
+                "{} does not exist at revision {}",
+                prefix.display(),
+                revision.name
+            )
+        })?;
+        tree = repo.find_tree(entry.id())?;
+    }
+
+    let mut blobs = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        let Some(name) = entry.name() else {
+            return TreeWalkResult::Skip;
+        };
+        if name.starts_with('.') {
+            return TreeWalkResult::Skip;
+        }
+        if entry.kind() == Some(ObjectType::Blob) {
+            blobs.push((format!("{}{}", dir, name), entry.id()));
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    Ok(blobs)
+}
+
+/// Path of the workspace root inside the repository's working directory
+fn workspace_prefix(repo: &Repository, root: &Path) -> Result<PathBuf> {
+    let workdir = repo
+        .workdir()
+        .context("Bare repositories are not supported")?
+        .canonicalize()?;
+
+    Ok(root
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .unwrap_or_default())
+}
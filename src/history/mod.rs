@@ -0,0 +1,527 @@
+//! Commit history search (`seekr log-search`)
+//!
+//! Architecture Decision: History lives in its own Tantivy index
+//! (`<workspace>/history.<n>/`) beside the code index, because:
+//! 1. Hunks and files are different documents; mixing them would skew BM25
+//!    statistics and every code search would have to exclude history
+//! 2. It is built on demand (`seekr index --history`) and can be rebuilt
+//!    without touching the code index
+//!
+//! One document is stored per diff hunk, carrying its commit's metadata and
+//! message, so a hit points at the exact change. Commits without indexable
+//! hunks (merges, lockfile bumps) get a single message-only document. The
+//! indexed HEAD is kept in the index's commit payload, so later runs only
+//! walk commits added since. An index in an older format is rebuilt, in a
+//! new generation like the code index, so `log-search` keeps working until
+//! the rebuild is published.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::ops::Bound;
+use std::path::Path;
+use std::time::Instant;
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
+use tantivy::schema::*;
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy};
+
+use crate::classify::FileClassifier;
+use crate::filter::SearchFilter;
+use crate::git::{self, CommitDiff};
+use crate::indexer::tokenizer::{self, CODE_TOKENIZER};
+use crate::indexer::{stale_version, write_version};
+use crate::lang;
+use crate::workspace::{StagedDir, Workspace};
+
+/// Format version of the history index; bump whenever its fields or tokenization change
+pub const HISTORY_VERSION: u32 = 1;
+
+/// Writer heap for history indexing (single-threaded, commits arrive in order)
+const WRITER_HEAP: usize = 50_000_000;
+/// Changed lines kept per hunk (the rest of a huge hunk is dropped)
+const MAX_HUNK_LINES: usize = 200;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Statistics from a history indexing run
+#[derive(Debug, Default)]
+pub struct HistoryStats {
+    pub commits_indexed: usize,
+    pub hunks_indexed: usize,
+    pub duration_secs: f64,
+}
+
+/// A matching hunk (or commit message) from `seekr log-search`
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryResult {
+    pub commit: String,
+    pub author: String,
+    /// Author date (YYYY-MM-DD, UTC)
+    pub date: String,
+    pub message: String,
+    /// None when only the commit message matched a commit without hunks
+    pub file_path: Option<String>,
+    pub hunk: Option<String>,
+    pub line: Option<usize>,
+    /// Added and removed lines, prefixed with `+` or `-`
+    pub changes: Vec<String>,
+    pub score: f32,
+}
+
+impl HistoryResult {
+    /// First line of the commit message
+    pub fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or("")
+    }
+
+    /// Changed lines containing a query term, or the first lines of the hunk
+    pub fn matching_changes(&self, query: &str, max: usize) -> Vec<&str> {
+        let query_lower = query.to_lowercase();
+        let terms: Vec<&str> = query_lower.split_whitespace().collect();
+
+        let matching: Vec<&str> = self
+            .changes
+            .iter()
+            .filter(|line| {
+                let line_lower = line.to_lowercase();
+                terms.iter().any(|term| line_lower.contains(term))
+            })
+            .map(String::as_str)
+            .take(max)
+            .collect();
+
+        if matching.is_empty() {
+            self.changes.iter().map(String::as_str).take(max).collect()
+        } else {
+            matching
+        }
+    }
+}
+
+/// Restrictions for a history search
+#[derive(Debug, Default)]
+pub struct HistoryFilter {
+    /// Words that must all appear in the author's name or email
+    author: Option<String>,
+    /// Only commits authored at or after this time (seconds since epoch)
+    since: Option<i64>,
+    paths: SearchFilter,
+}
+
+impl HistoryFilter {
+    /// Build a filter from CLI values (`since` is `YYYY-MM-DD`)
+    pub fn new(author: Option<String>, since: Option<&str>, paths: &[String]) -> Result<Self> {
+        Ok(Self {
+            author,
+            since: since.map(parse_date).transpose()?,
+            paths: SearchFilter::new(&[], paths, &[])?,
+        })
+    }
+}
+
+/// Resolved history schema fields
+#[derive(Debug, Clone, Copy)]
+struct HistoryFields {
+    commit: Field,
+    author: Field,
+    time: Field,
+    message: Field,
+    file_path: Field,
+    hunk: Field,
+    line: Field,
+    changes: Field,
+}
+
+impl HistoryFields {
+    fn new(schema: &Schema) -> Result<Self> {
+        let field = |name: &str| {
+            schema.get_field(name).with_context(|| {
+                format!(
+                    "History index is missing the `{}` field. Run `seekr index --history --force` to rebuild it.",
+                    name
+                )
+            })
+        };
+
+        Ok(Self {
+            commit: field("commit")?,
+            author: field("author")?,
+            time: field("time")?,
+            message: field("message")?,
+            file_path: field("file_path")?,
+            hunk: field("hunk")?,
+            line: field("line")?,
+            changes: field("changes")?,
+        })
+    }
+}
+
+/// Schema of the history index: one document per hunk
+fn build_schema() -> Schema {
+    let mut schema_builder = Schema::builder();
+    let code_text = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CODE_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+
+    // Commit id - raw, to check whether a commit is already indexed
+    schema_builder.add_text_field("commit", STRING | STORED);
+    // "Name <email>", split into words for `--author`
+    schema_builder.add_text_field("author", TEXT | STORED);
+    // Author time, range-filtered by `--since`
+    schema_builder.add_i64_field("time", INDEXED | STORED | FAST);
+    schema_builder.add_text_field("message", code_text.clone());
+    schema_builder.add_text_field("file_path", code_text.clone());
+    schema_builder.add_text_field("hunk", STORED);
+    schema_builder.add_u64_field("line", STORED);
+    schema_builder.add_text_field("changes", code_text);
+
+    schema_builder.build()
+}
+
+/// Indexes and searches the commit history of a workspace
+pub struct HistoryIndexer {
+    index: Index,
+    fields: HistoryFields,
+    reader: Option<IndexReader>,
+    /// Format version of an outdated index that was replaced when opening
+    upgraded_from: Option<u32>,
+    /// Generation being rebuilt, live once published
+    staged: Option<StagedDir>,
+}
+
+impl HistoryIndexer {
+    /// Create the history index of a workspace, or open it to add new commits
+    ///
+    /// With `force`, or when the live index is outdated, the index is rebuilt
+    /// in a new generation that `log-search` can't see until `publish`.
+    pub fn create(workspace: &Workspace, force: bool) -> Result<Self> {
+        let live_path = workspace.history_index_path();
+        let upgraded_from = stale_version(&live_path, HISTORY_VERSION);
+        let staged = if force || upgraded_from.is_some() {
+            Some(workspace.stage_history()?)
+        } else {
+            None
+        };
+        let index_path = staged.as_ref().map_or(live_path, StagedDir::path);
+        fs::create_dir_all(&index_path)?;
+
+        let index = Index::create_in_dir(&index_path, build_schema())
+            .or_else(|_| Index::open_in_dir(&index_path))?;
+        write_version(&index_path, HISTORY_VERSION)?;
+        tokenizer::register_tokenizers(&index);
+        let fields = HistoryFields::new(&index.schema())?;
+
+        Ok(Self {
+            index,
+            fields,
+            reader: None,
+            upgraded_from,
+            staged,
+        })
+    }
+
+    /// Make a rebuilt index live; a no-op for an index updated in place
+    pub fn publish(&mut self) -> Result<()> {
+        match self.staged.take() {
+            Some(staged) => staged.publish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Format version of the outdated index this indexer replaced, if any
+    pub fn upgraded_from(&self) -> Option<u32> {
+        self.upgraded_from
+    }
+
+    /// Open an existing history index for searching
+    pub fn open(index_path: &Path) -> Result<Self> {
+        if let Some(found) = stale_version(index_path, HISTORY_VERSION) {
+            bail!(
+                "The history index was built by another seekr version (format v{}, expected v{}). Run `seekr index --history` to rebuild it.",
+                found,
+                HISTORY_VERSION
+            );
+        }
+
+        let index = Index::open_in_dir(index_path)?;
+        tokenizer::register_tokenizers(&index);
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()?;
+
+        Ok(Self {
+            fields: HistoryFields::new(&index.schema())?,
+            index,
+            reader: Some(reader),
+            upgraded_from: None,
+            staged: None,
+        })
+    }
+
+    /// Index every commit reachable from HEAD that is not indexed yet
+    pub fn index_repository(&self, root: &Path) -> Result<HistoryStats> {
+        let start = Instant::now();
+        let f = self.fields;
+        let since = self.index.load_metas()?.payload;
+        let searcher = self.index.reader()?.searcher();
+        let classifier = FileClassifier::for_root(root)?;
+        let mut writer: IndexWriter = self.index.writer(WRITER_HEAP)?;
+        let mut stats = HistoryStats::default();
+
+        let is_indexed = |sha: &str| {
+            searcher
+                .doc_freq(&Term::from_field_text(f.commit, sha))
+                .is_ok_and(|count| count > 0)
+        };
+
+        let head = git::for_each_commit(root, since.as_deref(), is_indexed, |commit| {
+            stats.commits_indexed += 1;
+            stats.hunks_indexed += self.add_commit_documents(&writer, &classifier, commit)?;
+            Ok(())
+        })?;
+
+        let mut prepared = writer.prepare_commit()?;
+        if let Some(head) = &head {
+            prepared.set_payload(head);
+        }
+        prepared.commit()?;
+
+        stats.duration_secs = start.elapsed().as_secs_f64();
+        Ok(stats)
+    }
+
+    /// Add one document per indexable hunk, or a message-only document; returns the hunk count
+    fn add_commit_documents(
+        &self,
+        writer: &IndexWriter,
+        classifier: &FileClassifier,
+        commit: CommitDiff,
+    ) -> Result<usize> {
+        let f = &self.fields;
+        let commit_doc = || {
+            doc!(
+                f.commit => commit.sha.as_str(),
+                f.author => commit.author.as_str(),
+                f.time => commit.time,
+                f.message => commit.message.as_str(),
+            )
+        };
+
+        // Same files as the code index: known languages, no lockfiles or generated paths
+        let hunks: Vec<_> = commit
+            .hunks
+            .iter()
+            .filter(|hunk| {
+                lang::registry().detect(Path::new(&hunk.path)).is_some()
+                    && !classifier.is_generated_path(&hunk.path)
+            })
+            .collect();
+
+        if hunks.is_empty() {
+            writer.add_document(commit_doc())?;
+            return Ok(0);
+        }
+
+        for hunk in &hunks {
+            let mut document = commit_doc();
+            document.add_text(f.file_path, &hunk.path);
+            document.add_text(f.hunk, &hunk.header);
+            document.add_u64(f.line, hunk.new_start as u64);
+            let changes = &hunk.changes[..hunk.changes.len().min(MAX_HUNK_LINES)];
+            document.add_text(f.changes, changes.join("\n"));
+            writer.add_document(document)?;
+        }
+
+        Ok(hunks.len())
+    }
+
+    /// Search commit messages, changed lines and paths
+    pub fn search(
+        &self,
+        query_str: &str,
+        limit: usize,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<HistoryResult>> {
+        let f = &self.fields;
+        let reader = self
+            .reader
+            .as_ref()
+            .context("Index not opened for reading")?;
+        let searcher = reader.searcher();
+
+        let query_parser = QueryParser::new(
+            self.index.schema(),
+            vec![f.message, f.changes, f.file_path],
+            tokenizer::query_tokenizers(),
+        );
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+            vec![(Occur::Must, query_parser.parse_query(query_str)?)];
+
+        if let Some(author) = &filter.author {
+            let mut analyzer = self.index.tokenizer_for_field(f.author)?;
+            let mut tokens = analyzer.token_stream(author);
+            while tokens.advance() {
+                clauses.push((
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(f.author, &tokens.token().text),
+                        IndexRecordOption::Basic,
+                    )),
+                ));
+            }
+        }
+        if let Some(since) = filter.since {
+            clauses.push((
+                Occur::Must,
+                Box::new(RangeQuery::new_i64_bounds(
+                    "time".to_string(),
+                    Bound::Included(since),
+                    Bound::Unbounded,
+                )),
+            ));
+        }
+        let query = BooleanQuery::new(clauses);
+
+        // Path globs can't be expressed in the index, so page through hits until enough match
+        let page_size = if filter.paths.has_path_globs() {
+            limit.max(1) * 4
+        } else {
+            limit.max(1)
+        };
+        let mut results = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page =
+                searcher.search(&query, &TopDocs::with_limit(page_size).and_offset(offset))?;
+            let page_len = page.len();
+
+            for (score, doc_address) in page {
+                let document: TantivyDocument = searcher.doc(doc_address)?;
+                let file_path = get_text(&document, f.file_path);
+                if !filter.paths.matches_path(&file_path) || results.len() >= limit {
+                    continue;
+                }
+
+                let has_hunk = !file_path.is_empty();
+                results.push(HistoryResult {
+                    commit: get_text(&document, f.commit),
+                    author: get_text(&document, f.author),
+                    date: format_date(
+                        document
+                            .get_first(f.time)
+                            .and_then(|v| v.as_i64())
+                            .unwrap_or(0),
+                    ),
+                    message: get_text(&document, f.message),
+                    file_path: has_hunk.then_some(file_path),
+                    hunk: has_hunk.then(|| get_text(&document, f.hunk)),
+                    line: document
+                        .get_first(f.line)
+                        .and_then(|v| v.as_u64())
+                        .map(|line| line as usize),
+                    changes: get_text(&document, f.changes)
+                        .lines()
+                        .map(str::to_string)
+                        .collect(),
+                    score,
+                });
+            }
+
+            if results.len() >= limit || page_len < page_size {
+                break;
+            }
+            offset += page_size;
+        }
+
+        Ok(results)
+    }
+}
+
+/// Read a stored text field ("" when absent)
+fn get_text(doc: &TantivyDocument, field: Field) -> String {
+    doc.get_first(field)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Parse a `YYYY-MM-DD` date as midnight UTC (seconds since epoch)
+fn parse_date(value: &str) -> Result<i64> {
+    let invalid = || format!("Invalid date {:?} (expected YYYY-MM-DD)", value);
+
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        bail!(invalid());
+    };
+    let year: i64 = year.parse().with_context(invalid)?;
+    let month: u32 = month.parse().with_context(invalid)?;
+    let day: u32 = day.parse().with_context(invalid)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        bail!(invalid());
+    }
+
+    Ok(days_from_civil(year, month, day) * SECONDS_PER_DAY)
+}
+
+/// Format seconds since epoch as a `YYYY-MM-DD` date (UTC)
+fn format_date(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Years start in March so the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2024-03-01").unwrap(), 1_709_251_200);
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("last week").is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1_709_251_200 - 1), "2024-02-29");
+        assert_eq!(format_date(parse_date("2000-12-31").unwrap()), "2000-12-31");
+    }
+}
@@ -0,0 +1,118 @@
+//! The decision indexing makes for each walked file
+//!
+//! Shared by the full and incremental indexers, `seekr ls-files` and
+//! `seekr status`, so they all agree on what ends up in the index:
+//! containers are opened entry by entry, other files need a known language
+//! and must pass the classifier's size and content checks.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::archive;
+use crate::classify::{FileClassifier, SkipReason};
+use crate::encoding::SourceText;
+use crate::lang;
+use crate::notebook::{self, Notebook};
+use crate::workspace::Root;
+
+/// How indexing treats a walked file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Candidate {
+    /// An archive or notebook, indexed as one document per member or cell
+    Container,
+    /// A source file in a supported language
+    Source(&'static str),
+    /// No language is known for the file's extension or name
+    Unsupported,
+}
+
+impl Candidate {
+    pub fn of(path: &Path) -> Self {
+        if archive::is_archive(path) || notebook::is_notebook(path) {
+            return Candidate::Container;
+        }
+        match lang::registry().detect(path) {
+            Some(language) => Candidate::Source(language),
+            None => Candidate::Unsupported,
+        }
+    }
+
+    pub fn is_indexable(&self) -> bool {
+        *self != Candidate::Unsupported
+    }
+}
+
+/// Read a source file, or the reason it is skipped (binary, generated, oversized)
+pub fn read_source(
+    classifier: &FileClassifier,
+    root: &Root,
+    path: &Path,
+) -> Result<SourceText, SkipReason> {
+    classifier.read(path, &root.relative_path(path))
+}
+
+/// An archive member or notebook cell, checked as a file on its own
+pub struct Entry<'a> {
+    /// Path as stored in the index and shown in results
+    pub path: String,
+    pub language: &'a str,
+    pub text: Result<SourceText, SkipReason>,
+}
+
+/// Call `visit` with every member of an archive or cell of a notebook
+///
+/// The outer error is one returned by `visit`. The inner one means the
+/// container can't be read; entries visited before it must then be dropped,
+/// since a half-read container is not indexed.
+pub fn for_each_entry<F>(
+    classifier: &FileClassifier,
+    root: &Root,
+    path: &Path,
+    mut visit: F,
+) -> Result<Result<()>>
+where
+    F: FnMut(Entry<'_>) -> Result<()>,
+{
+    let container_path = root.workspace_path(path);
+    let relative_path = root.relative_path(path);
+
+    if notebook::is_notebook(path) {
+        let notebook = match Notebook::read(path) {
+            Ok(notebook) => notebook,
+            Err(e) => return Ok(Err(e)),
+        };
+        for (cell, language) in notebook.indexed_cells(classifier.notebook_markdown()) {
+            let cell_relative = notebook::cell_path(&relative_path, cell.number);
+            visit(Entry {
+                path: notebook::cell_path(&container_path, cell.number),
+                language,
+                text: classifier.check_bytes(&cell_relative, cell.source.clone().into_bytes()),
+            })?;
+        }
+        return Ok(Ok(()));
+    }
+
+    // Errors from `visit` stop the walk over members like read errors, but are passed on
+    let mut visit_error = None;
+    let result = archive::for_each_member(
+        path,
+        classifier.max_file_size(),
+        |member, language, bytes| {
+            let member_relative = archive::member_path(&relative_path, member);
+            let entry = Entry {
+                path: archive::member_path(&container_path, member),
+                language,
+                text: classifier.check_bytes(&member_relative, bytes),
+            };
+            if let Err(e) = visit(entry) {
+                visit_error = Some(e);
+                anyhow::bail!("Stopped by an error while visiting members");
+            }
+            Ok(())
+        },
+    );
+    match visit_error {
+        Some(e) => Err(e),
+        None => Ok(result),
+    }
+}
@@ -0,0 +1,448 @@
+//! Regular expression search for `seekr search --regex`
+//!
+//! A regex can't be run against the inverted index, but the literal text it
+//! requires can. Identifiers inside required literals are looked up in the
+//! content field's term dictionary, which holds every identifier whole and
+//! lowercased:
+//! - a word delimited on both sides (`\bparse\b`, `fn parse(`) is one term
+//! - a word cut off by the rest of the pattern (`parse\w+`) is a prefix,
+//!   suffix or infix match over the dictionary, if it can't straddle the
+//!   sub-words that identifiers too long to index whole are indexed as
+//!   (no `_`, case changes or `(?i)` letters)
+//!
+//! Only files containing all required words are read and matched line by
+//! line, so results are exactly what grep finds, with byte offsets of every
+//! match. Patterns without a usable literal (`\w+\(`) scan every file, and
+//! matches spanning lines are not found.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{BooleanQuery, Occur, Query, RegexQuery, TermQuery};
+use tantivy::schema::*;
+use tantivy::DocAddress;
+
+use super::schema::{SearchResult, DOC_TYPE_FILE};
+use super::tokenizer::{split_identifier, MAX_TOKEN_LEN};
+use super::{get_text, with_language_filter, Indexer};
+use crate::filter::SearchFilter;
+
+/// Matching lines kept per file (every match still counts toward the score)
+const MAX_MATCHING_LINES: usize = 10;
+
+/// Words shorter than this aren't worth a prefix/suffix/infix scan of the dictionary
+const MIN_PARTIAL_WORD: usize = 3;
+
+impl Indexer {
+    /// Search file contents for a regular expression
+    ///
+    /// Each result's score is its number of matches; files are taken in
+    /// index order until `limit` of them match.
+    pub fn search_regex(
+        &self,
+        pattern: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>> {
+        let regex = Regex::new(pattern).context("Invalid regular expression")?;
+        let hir = regex_syntax::parse(pattern).context("Invalid regular expression")?;
+
+        let reader = self
+            .reader
+            .as_ref()
+            .context("Index not opened for reading")?;
+        let searcher = reader.searcher();
+        let f = &self.fields;
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(
+            Occur::Must,
+            Box::new(TermQuery::new(
+                Term::from_field_text(f.doc_type, DOC_TYPE_FILE),
+                IndexRecordOption::Basic,
+            )),
+        )];
+        if let Some(requirement) = Requirement::of(&hir) {
+            clauses.push((Occur::Must, requirement.query(f.content)?));
+        }
+        let query = with_language_filter(Box::new(BooleanQuery::new(clauses)), f.language, filter);
+
+        let mut candidates: Vec<DocAddress> = searcher
+            .search(&query, &DocSetCollector)?
+            .into_iter()
+            .collect();
+        candidates.sort();
+
+        let mut results = Vec::new();
+        for doc_address in candidates {
+            if results.len() >= limit {
+                break;
+            }
+            let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
+            let file_path = get_text(&retrieved_doc, f.file_path);
+            if !filter.matches_path(&file_path) {
+                continue;
+            }
+
+            let content = get_text(&retrieved_doc, f.content);
+            let (matches, matching_lines, match_spans) = find_regex_matches(&content, &regex);
+            if matches == 0 {
+                continue;
+            }
+
+            let revision = get_text(&retrieved_doc, f.revision);
+            let encoding = get_text(&retrieved_doc, f.encoding);
+            results.push(SearchResult {
+                file_path,
+                language: get_text(&retrieved_doc, f.language),
+                score: matches as f32,
+                content,
+                matching_lines,
+                match_spans,
+                revision: (!revision.is_empty()).then_some(revision),
+                encoding: (!encoding.is_empty()).then_some(encoding),
+            });
+        }
+
+        results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        Ok(results)
+    }
+}
+
+type MatchingLines = Vec<(usize, String)>;
+type MatchSpans = Vec<Vec<(usize, usize)>>;
+
+/// Number of matches in `content`, and the first matching lines with the
+/// byte range of each match within its line
+fn find_regex_matches(content: &str, regex: &Regex) -> (usize, MatchingLines, MatchSpans) {
+    let mut matches = 0;
+    let mut matching_lines = Vec::new();
+    let mut match_spans = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let spans: Vec<(usize, usize)> = regex
+            .find_iter(line)
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        if spans.is_empty() {
+            continue;
+        }
+        matches += spans.len();
+        if matching_lines.len() < MAX_MATCHING_LINES {
+            matching_lines.push((i + 1, line.to_string())); // 1-indexed lines
+            match_spans.push(spans);
+        }
+    }
+
+    (matches, matching_lines, match_spans)
+}
+
+/// Indexed words a document must contain to possibly match a pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirement {
+    /// A whole identifier
+    Term(String),
+    /// An identifier starting with the word
+    Prefix(String),
+    /// An identifier ending with the word
+    Suffix(String),
+    /// An identifier containing the word
+    Infix(String),
+    All(Vec<Requirement>),
+    Any(Vec<Requirement>),
+}
+
+/// One character position of a concatenation, as far as word boundaries go
+#[derive(Debug, Clone, Copy)]
+enum Atom {
+    /// A known character
+    Char(char),
+    /// A letter in either case (a class like `[Tt]`), as its lowercase
+    Folded(char),
+    /// Something that never continues an identifier: punctuation, whitespace,
+    /// a word boundary or the start or end of the line
+    Delimiter,
+    /// Anything else, possibly empty or part of an identifier
+    Unknown,
+}
+
+impl Requirement {
+    /// What documents matching `hir` must contain, or None if the index can't tell
+    fn of(hir: &Hir) -> Option<Requirement> {
+        if let HirKind::Alternation(branches) = hir.kind() {
+            return branches
+                .iter()
+                .map(Requirement::of)
+                .collect::<Option<Vec<_>>>()
+                .map(Requirement::Any);
+        }
+
+        let mut atoms = Vec::new();
+        let mut requirements = Vec::new();
+        flatten(hir, &mut atoms, &mut requirements);
+        requirements.extend(words(&atoms));
+
+        match requirements.len() {
+            0 => None,
+            1 => requirements.pop(),
+            _ => Some(Requirement::All(requirements)),
+        }
+    }
+
+    /// The requirement as a query on the content field
+    fn query(&self, field: Field) -> Result<Box<dyn Query>> {
+        let dictionary_regex = |pattern: String| -> Result<Box<dyn Query>> {
+            Ok(Box::new(
+                RegexQuery::from_pattern(&pattern, field).context("Invalid term pattern")?,
+            ))
+        };
+        // Words are identifier characters only, which need no escaping
+        match self {
+            Requirement::Term(word) => Ok(Box::new(TermQuery::new(
+                Term::from_field_text(field, word),
+                IndexRecordOption::Basic,
+            ))),
+            Requirement::Prefix(word) => dictionary_regex(format!("{}.*", word)),
+            Requirement::Suffix(word) => dictionary_regex(format!(".*{}", word)),
+            Requirement::Infix(word) => dictionary_regex(format!(".*{}.*", word)),
+            Requirement::All(requirements) | Requirement::Any(requirements) => {
+                let occur = match self {
+                    Requirement::All(_) => Occur::Must,
+                    _ => Occur::Should,
+                };
+                Ok(Box::new(BooleanQuery::new(
+                    requirements
+                        .iter()
+                        .map(|requirement| Ok((occur, requirement.query(field)?)))
+                        .collect::<Result<Vec<_>>>()?,
+                )))
+            }
+        }
+    }
+}
+
+/// Lay out a concatenation as atoms, collecting requirements of the
+/// sub-expressions (groups, alternations) that can't be laid out
+fn flatten(hir: &Hir, atoms: &mut Vec<Atom>, requirements: &mut Vec<Requirement>) {
+    match hir.kind() {
+        HirKind::Empty => {}
+        HirKind::Literal(literal) => match std::str::from_utf8(&literal.0) {
+            Ok(text) => atoms.extend(text.chars().map(Atom::Char)),
+            Err(_) => atoms.push(Atom::Unknown),
+        },
+        HirKind::Class(class) => atoms.push(class_atom(class)),
+        HirKind::Look(look) => atoms.push(look_atom(*look)),
+        HirKind::Capture(capture) => flatten(&capture.sub, atoms, requirements),
+        HirKind::Concat(subs) => {
+            for sub in subs {
+                flatten(sub, atoms, requirements);
+            }
+        }
+        HirKind::Repetition(repetition) if repetition.min > 0 => {
+            // `\s+` and `[(,]+` still separate identifiers
+            match repetition.sub.kind() {
+                HirKind::Class(class) if matches!(class_atom(class), Atom::Delimiter) => {
+                    atoms.push(Atom::Delimiter)
+                }
+                _ => {
+                    atoms.push(Atom::Unknown);
+                    requirements.extend(Requirement::of(&repetition.sub));
+                }
+            }
+        }
+        HirKind::Repetition(_) => atoms.push(Atom::Unknown),
+        HirKind::Alternation(_) => {
+            atoms.push(Atom::Unknown);
+            requirements.extend(Requirement::of(hir));
+        }
+    }
+}
+
+fn class_atom(class: &Class) -> Atom {
+    let Class::Unicode(class) = class else {
+        return Atom::Unknown;
+    };
+    let mut members = class
+        .ranges()
+        .iter()
+        .flat_map(|range| range.start()..=range.end());
+
+    // A letter in either case, as `(?i)` produces: `[Kk\u{212A}]`, `[Ssſ]`.
+    // Folds to the long s are taken as `s`, as they virtually never occur in code.
+    if class.ranges().len() <= 3 {
+        let chars: Vec<char> = members.clone().take(5).collect();
+        let lower = chars.iter().find(|c| c.is_ascii_lowercase());
+        if let Some(&lower) = lower {
+            let upper = lower.to_ascii_uppercase();
+            if chars.len() <= 4
+                && chars
+                    .iter()
+                    .all(|&c| c.to_lowercase().eq([lower]) || c.to_uppercase().eq([upper]))
+            {
+                return Atom::Folded(lower);
+            }
+        }
+    }
+
+    if members.any(is_ident_char) {
+        Atom::Unknown
+    } else {
+        Atom::Delimiter
+    }
+}
+
+fn look_atom(look: Look) -> Atom {
+    match look {
+        Look::Start
+        | Look::End
+        | Look::StartLF
+        | Look::EndLF
+        | Look::StartCRLF
+        | Look::EndCRLF
+        | Look::WordUnicode
+        | Look::WordStartUnicode
+        | Look::WordEndUnicode
+        | Look::WordStartHalfUnicode
+        | Look::WordEndHalfUnicode => Atom::Delimiter,
+        _ => Atom::Unknown,
+    }
+}
+
+/// Requirements for the identifier words in a row of atoms
+fn words(atoms: &[Atom]) -> Vec<Requirement> {
+    let mut requirements = Vec::new();
+    let mut word = String::new();
+    // Whether the current word has letters of either case
+    let mut folded = false;
+    let mut delimited_before = false;
+    // Whether the atom before the current word is a delimiter
+    let mut after_delimiter = false;
+
+    for atom in atoms.iter().copied().chain([Atom::Unknown]) {
+        match atom {
+            Atom::Char(c) | Atom::Folded(c) if is_ident_char(c) => {
+                if word.is_empty() {
+                    delimited_before = after_delimiter;
+                    folded = false;
+                }
+                word.push(c);
+                folded |= matches!(atom, Atom::Folded(_));
+            }
+            _ => {
+                let delimiter = !matches!(atom, Atom::Unknown);
+                if !word.is_empty() {
+                    requirements.extend(word_requirement(
+                        &std::mem::take(&mut word),
+                        folded,
+                        delimited_before,
+                        delimiter,
+                    ));
+                }
+                after_delimiter = delimiter;
+            }
+        }
+    }
+    requirements
+}
+
+fn word_requirement(
+    word: &str,
+    folded: bool,
+    delimited_before: bool,
+    delimited_after: bool,
+) -> Option<Requirement> {
+    let lowercase = word.to_lowercase();
+    // Identifiers this long are only indexed as their sub-words
+    if lowercase.len() >= MAX_TOKEN_LEN {
+        return None;
+    }
+    if delimited_before && delimited_after {
+        return Some(Requirement::Term(lowercase));
+    }
+
+    // The identifier around a partial word may be too long to be indexed
+    // whole, so the word must fall within one of its sub-words
+    if lowercase.chars().count() < MIN_PARTIAL_WORD || folded || may_span_subwords(word) {
+        return None;
+    }
+    Some(match (delimited_before, delimited_after) {
+        (true, _) => Requirement::Prefix(lowercase),
+        (_, true) => Requirement::Suffix(lowercase),
+        _ => Requirement::Infix(lowercase),
+    })
+}
+
+/// Whether the tokenizer could split an identifier inside `word`; letters of
+/// either case could be split anywhere, so callers leave those words out
+fn may_span_subwords(word: &str) -> bool {
+    // Two capitals at the end split before the last one if a lowercase letter follows
+    let mut last_two = word.chars().rev().take(2);
+    let ends_in_capitals = word.chars().count() >= 2 && last_two.all(char::is_uppercase);
+
+    word.contains('_') || split_identifier(word).len() > 1 || ends_in_capitals
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirement(pattern: &str) -> Option<Requirement> {
+        Requirement::of(&regex_syntax::parse(pattern).unwrap())
+    }
+
+    #[test]
+    fn test_requirements() {
+        use Requirement::*;
+        let word = |w: &str| w.to_string();
+
+        assert_eq!(
+            requirement(r"fn\s+parse_header\("),
+            Some(Term(word("parse_header")))
+        );
+        assert_eq!(
+            requirement(r"\bHttpClient::new"),
+            Some(All(vec![Term(word("httpclient")), Prefix(word("new"))]))
+        );
+        assert_eq!(requirement(r"(?i)\bParse\b"), Some(Term(word("parse"))));
+
+        // Partial words that a long identifier's sub-words could split
+        assert_eq!(requirement(r"(?i)todo"), None);
+        assert_eq!(requirement(r"\bfoo_ba"), None);
+        assert_eq!(requirement(r"getUser"), None);
+        assert_eq!(requirement(r"\bHTTP"), None);
+        assert_eq!(requirement(r"\bhttp"), Some(Prefix(word("http"))));
+        assert_eq!(
+            requirement(r"^(use|import) serde"),
+            Some(All(vec![
+                Any(vec![Infix(word("use")), Infix(word("import"))]),
+                Prefix(word("serde")),
+            ]))
+        );
+        assert_eq!(requirement(r"\w+\(\)"), None);
+        assert_eq!(requirement(r"foo|\d+"), None);
+    }
+
+    #[test]
+    fn test_long_identifiers() {
+        use Requirement::*;
+
+        // Tokens of MAX_TOKEN_LEN bytes are dropped from the index
+        let longest = "a".repeat(MAX_TOKEN_LEN - 1);
+        assert_eq!(
+            requirement(&format!(r"\b{}\b", longest)),
+            Some(Term(longest.clone()))
+        );
+        let too_long = "a".repeat(MAX_TOKEN_LEN);
+        assert_eq!(requirement(&format!(r"\b{}\b", too_long)), None);
+        assert_eq!(requirement(&format!(r"\b{}", too_long)), None);
+
+        // Lowercasing can make a word longer ('İ' is 2 bytes, "i̇" is 3)
+        let grows = "İ".repeat(MAX_TOKEN_LEN / 2 - 1);
+        assert!(grows.len() < MAX_TOKEN_LEN);
+        assert_eq!(requirement(&format!(r"\b{}\b", grows)), None);
+    }
+}
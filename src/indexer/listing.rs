@@ -0,0 +1,148 @@
+//! What indexing does with each file, for `seekr ls-files` and `seekr index --dry-run`
+//!
+//! The roots are walked and every file is checked exactly as
+//! `Indexer::index_directory` does, without touching the index. Archives
+//! and notebooks are listed by member and cell, under the paths results use.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::candidate::{for_each_entry, read_source, Candidate};
+use crate::classify::{FileClassifier, SkipReason};
+use crate::lang;
+use crate::workspace::Root;
+
+/// What indexing does with a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDecision {
+    Indexed,
+    /// In a supported language, but left out by a size or content check
+    Skipped(SkipReason),
+    /// No language is known for the file's extension or name
+    Unsupported,
+    /// Left out of the walk by `.gitignore`, `.seekrignore` or as a hidden path
+    Ignored,
+}
+
+impl FileDecision {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileDecision::Indexed => "indexed",
+            FileDecision::Skipped(_) => "skipped",
+            FileDecision::Unsupported => "unsupported",
+            FileDecision::Ignored => "ignored",
+        }
+    }
+}
+
+/// A file (or archive member, or notebook cell) and what indexing does with it
+#[derive(Debug, Clone)]
+pub struct ListedFile {
+    /// Path as stored in the index and shown in results
+    pub path: String,
+    pub language: Option<String>,
+    pub decision: FileDecision,
+}
+
+/// Every file under `roots` with its indexing decision, sorted by path
+///
+/// With `include_ignored`, files the walk leaves out are listed as well.
+pub fn list_files(
+    roots: &[Root],
+    threads: usize,
+    include_ignored: bool,
+) -> Result<Vec<ListedFile>> {
+    let listed = Mutex::new(Vec::new());
+
+    for root in roots {
+        let classifier = FileClassifier::for_root(&root.path)?;
+        let walked: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+
+        root.walker(roots).threads(threads).for_each_file(|path| {
+            walked.lock().unwrap().insert(path.to_path_buf());
+            let files = decide(&classifier, root, path);
+            listed.lock().unwrap().extend(files);
+            Ok(())
+        })?;
+
+        if include_ignored {
+            let walked = walked.into_inner().unwrap();
+            root.walker(roots)
+                .threads(threads)
+                .ignore_files(false)
+                .skip_hidden(false)
+                .for_each_file(|path| {
+                    if !walked.contains(path) && !in_git_dir(root, path) {
+                        listed.lock().unwrap().push(ListedFile {
+                            path: root.workspace_path(path),
+                            language: lang::registry().detect(path).map(String::from),
+                            decision: FileDecision::Ignored,
+                        });
+                    }
+                    Ok(())
+                })?;
+        }
+    }
+
+    let mut listed = listed.into_inner().unwrap();
+    listed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(listed)
+}
+
+/// The decision for a walked file, as `index_directory` makes it
+fn decide(classifier: &FileClassifier, root: &Root, path: &Path) -> Vec<ListedFile> {
+    let language = match Candidate::of(path) {
+        Candidate::Container => return container_entries(classifier, root, path),
+        Candidate::Source(language) => Some(language),
+        Candidate::Unsupported => None,
+    };
+    let decision = match language {
+        None => FileDecision::Unsupported,
+        Some(_) => match read_source(classifier, root, path) {
+            Ok(_) => FileDecision::Indexed,
+            Err(reason) => FileDecision::Skipped(reason),
+        },
+    };
+    vec![ListedFile {
+        path: root.workspace_path(path),
+        language: language.map(String::from),
+        decision,
+    }]
+}
+
+/// Archive members or notebook cells, or only the container when it can't
+/// be read, since indexing then keeps none of it
+fn container_entries(classifier: &FileClassifier, root: &Root, path: &Path) -> Vec<ListedFile> {
+    let mut entries = Vec::new();
+    let result = for_each_entry(classifier, root, path, |entry| {
+        entries.push(ListedFile {
+            path: entry.path,
+            language: Some(entry.language.to_string()),
+            decision: match entry.text {
+                Ok(_) => FileDecision::Indexed,
+                Err(reason) => FileDecision::Skipped(reason),
+            },
+        });
+        Ok(())
+    });
+    match result {
+        Ok(Ok(())) => entries,
+        _ => vec![unreadable(root.workspace_path(path))],
+    }
+}
+
+fn unreadable(path: String) -> ListedFile {
+    ListedFile {
+        path,
+        language: None,
+        decision: FileDecision::Skipped(SkipReason::Unreadable),
+    }
+}
+
+/// Git's own files are never candidates, ignored or not
+fn in_git_dir(root: &Root, path: &Path) -> bool {
+    path.strip_prefix(&root.path)
+        .is_ok_and(|relative| relative.components().any(|c| c.as_os_str() == ".git"))
+}
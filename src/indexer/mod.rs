@@ -4,28 +4,61 @@
 //! - Best-in-class BM25 implementation in Rust
 //! - Fast incremental updates
 //! - Excellent memory efficiency
-//! - Supports custom tokenizers for code
+//! - Supports custom tokenizers for code (see `tokenizer`)
 
+mod candidate;
+mod grep;
+mod listing;
 mod schema;
+mod symbols;
+pub mod tokenizer;
 
 use anyhow::{Context, Result};
-use ignore::WalkBuilder;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::Instant;
-use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
+use tantivy::collector::{Count, DocSetCollector, TopDocs};
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
 use tantivy::schema::*;
 use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy};
 
-pub use schema::SearchResult;
+use crate::cache::FileCache;
+use crate::chunker::{Chunker, Language};
+use crate::classify::{FileClassifier, SkipReason};
+use crate::encoding::SourceText;
+use crate::filter::SearchFilter;
+use crate::git::{self, Revision};
+use crate::lang;
+use crate::lock::write_atomic;
+use crate::walk::resolve_threads;
+use crate::workspace::{owning_root, Root, StagedDir, Workspace};
+
+pub use candidate::{read_source, Candidate};
+pub use listing::{list_files, FileDecision};
+pub use schema::{stale_version, write_version, ChunkSearchResult, SearchResult, SCHEMA_VERSION};
+pub use symbols::SymbolMatch;
+
+use schema::{Fields, DOC_TYPE_CHUNK, DOC_TYPE_FILE};
+
+/// Index writer memory budget per indexing thread (25MB)
+const WRITER_HEAP_PER_THREAD: usize = 25_000_000;
 
 /// Statistics from an indexing operation
 #[derive(Debug, Default)]
 pub struct IndexStats {
     pub files_indexed: usize,
+    pub files_deleted: usize,
     pub total_lines: usize,
     pub duration_secs: f64,
+    /// Workspace-relative paths of files removed from the index since the
+    /// last run (deleted from disk, or now skipped)
+    pub deleted_files: Vec<String>,
+    /// Files not indexed this run, by reason (binary, generated, too large...)
+    pub skipped: BTreeMap<SkipReason, usize>,
 }
 
 /// Index health status
@@ -36,48 +69,195 @@ pub struct IndexStatus {
     pub healthy: bool,
 }
 
+/// Indexed files and lines of one language
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub lines: usize,
+}
+
 /// Main indexer wrapping Tantivy
 pub struct Indexer {
     index: Index,
     schema: Schema,
+    fields: Fields,
     reader: Option<IndexReader>,
+    /// Also index chunker output as separate documents
+    index_chunks: bool,
+    chunker: Chunker,
+    /// Worker threads for walking and indexing (0 = one per core)
+    threads: usize,
+    /// Commit id tagged onto every document of a revision snapshot
+    revision: Option<String>,
+    /// Format version of an outdated index that was replaced when opening
+    upgraded_from: Option<u32>,
+    /// Directory of the index
+    path: PathBuf,
+    /// Generation being rebuilt, until it is published
+    staged: Option<StagedDir>,
 }
 
 impl Indexer {
-    /// Get the default index path (~/.seekr/index)
-    pub fn default_index_path() -> Result<PathBuf> {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        Ok(home.join(".seekr").join("index"))
+    /// Create a new indexer for a workspace
+    ///
+    /// With `force`, or when the live index is outdated, the index is rebuilt
+    /// in a new generation that searches can't see until `publish`.
+    pub fn new(workspace: &Workspace, force: bool) -> Result<Self> {
+        let indexer =
+            Self::open_or_stage(&workspace.index_path(), force, || workspace.stage_index())?;
+        Self::write_workspace_file(&indexer.path, workspace)?;
+
+        // Older versions kept the file cache next to the index instead of inside it
+        let legacy_cache = workspace.data_dir().join(FileCache::FILE_NAME);
+        if legacy_cache.exists() {
+            if indexer.staged.is_none() && !indexer.path.join(FileCache::FILE_NAME).exists() {
+                fs::rename(&legacy_cache, indexer.path.join(FileCache::FILE_NAME))?;
+            } else {
+                fs::remove_file(&legacy_cache)?;
+            }
+        }
+
+        Ok(indexer)
+    }
+
+    /// Store the workspace path in an index directory (`workspace.txt`)
+    pub fn write_workspace_file(index_path: &Path, workspace: &Workspace) -> Result<()> {
+        let meta_path = index_path.join("workspace.txt");
+        write_atomic(&meta_path, workspace.root().to_string_lossy().as_bytes())
     }
 
-    /// Create a new indexer (creates/overwrites index)
-    pub fn new(workspace_path: &Path, force: bool) -> Result<Self> {
-        let index_path = Self::default_index_path()?;
+    /// Directory of the index this indexer writes to
+    ///
+    /// For a rebuild this is the staged generation, which also holds its file cache.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
 
-        // Remove existing index if force or doesn't exist
-        if force && index_path.exists() {
-            fs::remove_dir_all(&index_path)?;
+    /// Make a rebuilt index live; a no-op for an index updated in place
+    pub fn publish(&mut self) -> Result<()> {
+        match self.staged.take() {
+            Some(staged) => staged.publish(),
+            None => Ok(()),
         }
-        fs::create_dir_all(&index_path)?;
+    }
 
-        let schema = schema::build_schema();
-        let index = Index::create_in_dir(&index_path, schema.clone())
-            .or_else(|_| Index::open_in_dir(&index_path))?;
+    /// Create an indexer for a git revision snapshot of a workspace
+    ///
+    /// Commits never change, so an existing snapshot index is only rebuilt with `force`.
+    pub fn for_revision(workspace: &Workspace, revision: &Revision, force: bool) -> Result<Self> {
+        let mut indexer = Self::create_at(&workspace.revision_index_path(&revision.sha), force)?;
+        indexer.revision = Some(revision.sha.clone());
+        Ok(indexer)
+    }
 
-        // Store workspace path in index metadata
-        let meta_path = index_path.join("workspace.txt");
-        fs::write(meta_path, workspace_path.to_string_lossy().as_bytes())?;
+    /// Create an indexer for the dependency sources of a workspace (see `deps`)
+    ///
+    /// Like the workspace index, a forced or format-upgrade rebuild is staged
+    /// until `publish`, so `--scope deps` searches keep working meanwhile.
+    pub fn for_dependencies(workspace: &Workspace, force: bool) -> Result<Self> {
+        Self::open_or_stage(&workspace.deps_index_path(), force, || workspace.stage_deps())
+    }
+
+    /// Open the live index at `live_path`, or rebuild it in a generation from
+    /// `stage` with `force` or when it is outdated
+    fn open_or_stage(
+        live_path: &Path,
+        force: bool,
+        stage: impl FnOnce() -> Result<StagedDir>,
+    ) -> Result<Self> {
+        let upgraded_from = stale_version(live_path, SCHEMA_VERSION);
+        if !force && upgraded_from.is_none() {
+            return Self::create_at(live_path, false);
+        }
+
+        let staged = stage()?;
+        let mut indexer = Self::create_at(&staged.path(), false)?;
+        indexer.upgraded_from = upgraded_from;
+        indexer.staged = Some(staged);
+        Ok(indexer)
+    }
+
+    /// Create or open the index at `index_path` (removing it first if `force`)
+    fn create_at(index_path: &Path, force: bool) -> Result<Self> {
+        // An index in another format can't be updated in place
+        let upgraded_from = stale_version(index_path, SCHEMA_VERSION);
+
+        // Remove existing index if force or outdated
+        if (force || upgraded_from.is_some()) && index_path.exists() {
+            fs::remove_dir_all(index_path)?;
+        }
+        fs::create_dir_all(index_path)?;
+
+        let index = Index::create_in_dir(index_path, schema::build_schema())
+            .or_else(|_| Index::open_in_dir(index_path))?;
+        write_version(index_path, SCHEMA_VERSION)?;
+        tokenizer::register_tokenizers(&index);
+        let schema = index.schema();
+        let fields = Fields::new(&schema)?;
 
         Ok(Self {
             index,
+            fields,
             schema,
             reader: None,
+            index_chunks: false,
+            chunker: Chunker::default(),
+            threads: 0,
+            revision: None,
+            upgraded_from,
+            path: index_path.to_path_buf(),
+            staged: None,
+        })
+    }
+
+    /// Format version of the outdated index this indexer replaced, if any
+    ///
+    /// A replaced index starts empty, so callers must re-index every file.
+    pub fn upgraded_from(&self) -> Option<u32> {
+        self.upgraded_from
+    }
+
+    /// Enable or disable chunk-level documents (functions, classes, blocks)
+    pub fn with_chunks(mut self, enabled: bool) -> Self {
+        self.index_chunks = enabled;
+        self
+    }
+
+    /// Set the number of worker threads for indexing (0 = one per core)
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Whether the index contains chunk-level documents
+    pub fn has_chunks(&self) -> bool {
+        has_documents(&self.index, self.fields.doc_type, DOC_TYPE_CHUNK)
+    }
+
+    /// Whether the index at `index_path` contains chunk-level documents,
+    /// even if it is in an outdated format
+    pub fn has_chunks_at(index_path: &Path) -> bool {
+        Index::open_in_dir(index_path).is_ok_and(|index| {
+            index
+                .schema()
+                .get_field("doc_type")
+                .is_ok_and(|doc_type| has_documents(&index, doc_type, DOC_TYPE_CHUNK))
         })
     }
 
     /// Open an existing index for searching
     pub fn open(index_path: &Path) -> Result<Self> {
+        if let Some(found) = stale_version(index_path, SCHEMA_VERSION) {
+            anyhow::bail!(
+                "The index at {} was built by another seekr version (format v{}, expected v{}). Run `seekr index` to rebuild it.",
+                index_path.display(),
+                found,
+                SCHEMA_VERSION
+            );
+        }
+
         let index = Index::open_in_dir(index_path)?;
+        tokenizer::register_tokenizers(&index);
         let schema = index.schema();
         let reader = index
             .reader_builder()
@@ -86,8 +266,16 @@ impl Indexer {
 
         Ok(Self {
             index,
+            fields: Fields::new(&schema)?,
             schema,
             reader: Some(reader),
+            index_chunks: false,
+            chunker: Chunker::default(),
+            threads: 0,
+            revision: None,
+            upgraded_from: None,
+            path: index_path.to_path_buf(),
+            staged: None,
         })
     }
 
@@ -107,236 +295,709 @@ impl Indexer {
         Ok(IndexStatus {
             num_docs: searcher.num_docs(),
             size_bytes,
-            healthy: true,
+            healthy: verify_index(index_path, SCHEMA_VERSION).is_empty(),
         })
     }
 
-    /// Index all files in a directory
-    pub fn index_directory(&mut self, path: &Path) -> Result<IndexStats> {
-        let start = Instant::now();
-        let mut stats = IndexStats::default();
+    /// Indexed files and lines per language, from the file documents
+    pub fn language_stats(&self) -> Result<BTreeMap<String, LanguageStats>> {
+        let reader = self
+            .reader
+            .as_ref()
+            .context("Index not opened for reading")?;
+        let searcher = reader.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(self.fields.doc_type, DOC_TYPE_FILE),
+            IndexRecordOption::Basic,
+        );
 
-        let mut writer: IndexWriter = self.index.writer(50_000_000)?; // 50MB heap
+        let mut stats: BTreeMap<String, LanguageStats> = BTreeMap::new();
+        for doc_address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            let entry = stats.entry(get_text(&doc, self.fields.language)).or_default();
+            entry.files += 1;
+            entry.lines += get_u64(&doc, self.fields.line_count) as usize;
+        }
+        Ok(stats)
+    }
 
-        // Use ignore crate to respect .gitignore
-        let walker = WalkBuilder::new(path)
-            .hidden(true)
-            .git_ignore(true)
-            .git_global(true)
-            .git_exclude(true)
-            .build();
+    /// Index all files of the workspace roots
+    pub fn index_directory(&mut self, roots: &[Root]) -> Result<IndexStats> {
+        let start = Instant::now();
+        let files_indexed = AtomicUsize::new(0);
+        let total_lines = AtomicUsize::new(0);
+        let skipped: Mutex<BTreeMap<SkipReason, usize>> = Mutex::new(BTreeMap::new());
+
+        let mut writer = self.writer(resolve_threads(self.threads))?;
+
+        for root in roots {
+            let walker = root.walker(roots).threads(self.threads);
+            let classifier = FileClassifier::for_root(&root.path)?;
+
+            // Use ignore crate to respect .gitignore; files are read and indexed on walker threads
+            walker.for_each_file(|entry_path| {
+                let language = match Candidate::of(entry_path) {
+                    Candidate::Container => {
+                        let (files, lines) = self
+                            .add_container_documents(
+                                &writer,
+                                &classifier,
+                                root,
+                                entry_path,
+                                &skipped,
+                            )?
+                            .unwrap_or_default();
+                        files_indexed.fetch_add(files, Ordering::Relaxed);
+                        total_lines.fetch_add(lines, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    Candidate::Source(language) => language,
+                    Candidate::Unsupported => return Ok(()),
+                };
+
+                // Read file content, skipping binary, generated and oversized files
+                let text = match read_source(&classifier, root, entry_path) {
+                    Ok(text) => text,
+                    Err(reason) => {
+                        *skipped.lock().unwrap().entry(reason).or_insert(0) += 1;
+                        return Ok(());
+                    }
+                };
+                let line_count = text.content.lines().count();
+
+                let workspace_path = root.workspace_path(entry_path);
+                self.add_file_documents(&writer, &workspace_path, &workspace_path, text, language)?;
+
+                files_indexed.fetch_add(1, Ordering::Relaxed);
+                total_lines.fetch_add(line_count, Ordering::Relaxed);
+                Ok(())
+            })?;
+        }
 
-        let file_path_field = self.schema.get_field("file_path").unwrap();
-        let content_field = self.schema.get_field("content").unwrap();
-        let language_field = self.schema.get_field("language").unwrap();
-        let line_count_field = self.schema.get_field("line_count").unwrap();
+        writer.commit()?;
 
-        for entry in walker.filter_map(|e| e.ok()) {
-            let entry_path = entry.path();
+        Ok(IndexStats {
+            files_indexed: files_indexed.into_inner(),
+            total_lines: total_lines.into_inner(),
+            duration_secs: start.elapsed().as_secs_f64(),
+            skipped: skipped.into_inner().unwrap(),
+            ..Default::default()
+        })
+    }
 
-            // Skip directories and non-text files
-            if !entry_path.is_file() {
-                continue;
-            }
+    /// Incrementally index only changed files of the workspace roots
+    pub fn index_directory_incremental(
+        &mut self,
+        roots: &[Root],
+        cache: &mut crate::cache::FileCache,
+    ) -> Result<IndexStats> {
+        use crate::cache::{FileFingerprint, FileStamp, FileStatus};
 
-            // Detect language from extension
-            let language = match entry_path.extension().and_then(|e| e.to_str()) {
-                Some("rs") => "rust",
-                Some("py") => "python",
-                Some("ts" | "tsx") => "typescript",
-                Some("js" | "jsx") => "javascript",
-                Some("go") => "go",
-                Some("java") => "java",
-                Some("c" | "h") => "c",
-                Some("cpp" | "hpp" | "cc") => "cpp",
-                Some("rb") => "ruby",
-                Some("md") => "markdown",
-                Some("toml") => "toml",
-                Some("yaml" | "yml") => "yaml",
-                Some("json") => "json",
-                _ => continue, // Skip unsupported file types
-            };
+        let start = Instant::now();
+        let mut stats = IndexStats::default();
+        let files_indexed = AtomicUsize::new(0);
+        let total_lines = AtomicUsize::new(0);
+        let skipped_files = AtomicUsize::new(0);
+        let skipped: Mutex<BTreeMap<SkipReason, usize>> = Mutex::new(BTreeMap::new());
+
+        // Every file still on disk; anything cached but not seen was deleted
+        let seen: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+        // New fingerprints (None = forget the file), applied once the walk is done
+        let updates: Mutex<Vec<(PathBuf, Option<FileFingerprint>)>> = Mutex::new(Vec::new());
+        // Previously indexed files that are now skipped
+        let now_skipped: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        let mut writer = self.writer(resolve_threads(self.threads))?;
+        let cache_ref = &*cache;
+
+        for root in roots {
+            let walker = root.walker(roots).threads(self.threads);
+            let classifier = FileClassifier::for_root(&root.path)?;
+
+            walker.for_each_file(|entry_path| {
+                seen.lock()
+                    .unwrap()
+                    .insert(entry_path.to_string_lossy().to_string());
+
+                let candidate = Candidate::of(entry_path);
+                if !candidate.is_indexable() {
+                    return Ok(());
+                }
+
+                // Check if file needs re-indexing
+                let status = cache_ref.check_file(entry_path);
+                match status {
+                    FileStatus::Unchanged => {
+                        skipped_files.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    FileStatus::Touched(fingerprint) => {
+                        // Same bytes, new mtime: refresh the fingerprint only
+                        updates
+                            .lock()
+                            .unwrap()
+                            .push((entry_path.to_path_buf(), Some(fingerprint)));
+                        skipped_files.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    FileStatus::New | FileStatus::Modified => {}
+                }
+
+                // Taken before reading, so the fingerprint never pairs newer
+                // metadata with older contents
+                let stamp = FileStamp::of(entry_path);
+                let workspace_path = root.workspace_path(entry_path);
+                if candidate == Candidate::Container {
+                    // Members share the container's source path, so this drops all of them
+                    writer.delete_term(Term::from_field_text(
+                        self.fields.source_path,
+                        &workspace_path,
+                    ));
+                    let indexed = self.add_container_documents(
+                        &writer,
+                        &classifier,
+                        root,
+                        entry_path,
+                        &skipped,
+                    )?;
+                    // An unreadable container is forgotten, so the next run tries it again
+                    let fingerprint = indexed
+                        .and(stamp)
+                        .and_then(|stamp| FileFingerprint::read(entry_path, stamp));
+                    updates
+                        .lock()
+                        .unwrap()
+                        .push((entry_path.to_path_buf(), fingerprint));
+                    let (files, lines) = indexed.unwrap_or_default();
+                    files_indexed.fetch_add(files, Ordering::Relaxed);
+                    total_lines.fetch_add(lines, Ordering::Relaxed);
+                    return Ok(());
+                }
+                let Candidate::Source(language) = candidate else {
+                    return Ok(());
+                };
+                let text = match read_source(&classifier, root, entry_path) {
+                    Ok(text) => text,
+                    Err(reason) => {
+                        *skipped.lock().unwrap().entry(reason).or_insert(0) += 1;
+
+                        // Remember cheap-to-recheck skips so they are not re-read every run;
+                        // oversized files are forgotten so a larger limit picks them up
+                        let fingerprint = match reason {
+                            SkipReason::TooLarge | SkipReason::Unreadable => None,
+                            _ => stamp.and_then(|stamp| FileFingerprint::read(entry_path, stamp)),
+                        };
+                        updates
+                            .lock()
+                            .unwrap()
+                            .push((entry_path.to_path_buf(), fingerprint));
+
+                        // A file that was indexed before must leave the index
+                        if status == FileStatus::Modified {
+                            writer.delete_term(Term::from_field_text(
+                                self.fields.source_path,
+                                &workspace_path,
+                            ));
+                            now_skipped.lock().unwrap().push(workspace_path);
+                        }
+                        return Ok(());
+                    }
+                };
+
+                // The cache hashes the bytes on disk, which transcoded text no longer matches
+                let fingerprint = stamp.and_then(|stamp| match text.encoding {
+                    None => FileFingerprint::from_content(entry_path, stamp, text.content.as_bytes()),
+                    Some(_) => FileFingerprint::read(entry_path, stamp),
+                });
+                updates
+                    .lock()
+                    .unwrap()
+                    .push((entry_path.to_path_buf(), fingerprint));
+
+                let line_count = text.content.lines().count();
+
+                // Replace the previous version of this file, if any
+                writer.delete_term(Term::from_field_text(self.fields.source_path, &workspace_path));
+                self.add_file_documents(
+                    &writer,
+                    &workspace_path,
+                    &workspace_path,
+                    text,
+                    language,
+                )?;
+
+                files_indexed.fetch_add(1, Ordering::Relaxed);
+                total_lines.fetch_add(line_count, Ordering::Relaxed);
+                Ok(())
+            })?;
+        }
 
-            // Read file content
-            let content = match fs::read_to_string(entry_path) {
-                Ok(c) => c,
-                Err(_) => continue, // Skip binary/unreadable files
+        for (entry_path, fingerprint) in updates.into_inner().unwrap() {
+            match fingerprint {
+                Some(fingerprint) => cache.record(&entry_path, fingerprint),
+                None => cache.forget(&entry_path),
+            }
+        }
+        stats.deleted_files = now_skipped.into_inner().unwrap();
+
+        // Purge files that disappeared since the last run
+        for removed in cache.remove_missing(&seen.into_inner().unwrap()) {
+            let removed_path = Path::new(&removed);
+            let workspace_path = match owning_root(roots, removed_path) {
+                Some(root) => root.workspace_path(removed_path),
+                None => removed,
             };
 
-            let line_count = content.lines().count();
-            let relative_path = entry_path
-                .strip_prefix(path)
-                .unwrap_or(entry_path)
-                .to_string_lossy();
-
-            writer.add_document(doc!(
-                file_path_field => relative_path.to_string(),
-                content_field => content,
-                language_field => language,
-                line_count_field => line_count as u64
-            ))?;
-
-            stats.files_indexed += 1;
-            stats.total_lines += line_count;
+            writer.delete_term(Term::from_field_text(self.fields.source_path, &workspace_path));
+            stats.files_deleted += 1;
+            stats.deleted_files.push(workspace_path);
         }
 
         writer.commit()?;
+        cache.save()?;
+
+        stats.files_indexed = files_indexed.into_inner();
+        stats.total_lines = total_lines.into_inner();
+        stats.skipped = skipped.into_inner().unwrap();
         stats.duration_secs = start.elapsed().as_secs_f64();
 
+        tracing::info!(
+            "Incremental index: {} changed, {} unchanged, {} deleted",
+            stats.files_indexed,
+            skipped_files.into_inner(),
+            stats.files_deleted
+        );
+
         Ok(stats)
     }
 
-    /// Incrementally index only changed files
-    pub fn index_directory_incremental(
+    /// Drop the documents of files that move to another root, or out of the
+    /// workspace, when its roots change from `before` to `after`
+    ///
+    /// Their cache entries are forgotten too, so the next incremental run
+    /// indexes them under their new workspace path. Returns the dropped paths.
+    pub fn apply_root_change(
         &mut self,
-        path: &Path,
-        cache: &mut crate::cache::FileCache,
-    ) -> Result<IndexStats> {
-        use crate::cache::FileStatus;
-        
-        let start = Instant::now();
-        let mut stats = IndexStats::default();
-        let mut changed_files = 0;
-        let mut skipped_files = 0;
+        before: &[Root],
+        after: &[Root],
+        cache: &mut FileCache,
+    ) -> Result<Vec<String>> {
+        let mut moved = Vec::new();
+        for path in cache.paths() {
+            let path = Path::new(path);
+            let Some(old_root) = owning_root(before, path) else {
+                continue;
+            };
+            let old_path = old_root.workspace_path(path);
+            let new_path = owning_root(after, path).map(|root| root.workspace_path(path));
+            if new_path.as_ref() != Some(&old_path) {
+                moved.push((path.to_path_buf(), old_path));
+            }
+        }
+        if moved.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        let mut writer: IndexWriter = self.index.writer(50_000_000)?;
+        let mut writer = self.writer(1)?;
+        for (_, old_path) in &moved {
+            writer.delete_term(Term::from_field_text(self.fields.source_path, old_path));
+        }
+        writer.commit()?;
 
-        let walker = WalkBuilder::new(path)
-            .hidden(true)
-            .git_ignore(true)
-            .git_global(true)
-            .git_exclude(true)
-            .build();
+        for (path, _) in &moved {
+            cache.forget(path);
+        }
+        cache.save()?;
 
-        let file_path_field = self.schema.get_field("file_path").unwrap();
-        let content_field = self.schema.get_field("content").unwrap();
-        let language_field = self.schema.get_field("language").unwrap();
-        let line_count_field = self.schema.get_field("line_count").unwrap();
+        Ok(moved.into_iter().map(|(_, old_path)| old_path).collect())
+    }
 
-        for entry in walker.filter_map(|e| e.ok()) {
-            let entry_path = entry.path();
+    /// Index the files of a git revision straight from the object database
+    ///
+    /// Paths are relative to the workspace root, as in the working-tree index.
+    pub fn index_revision(&mut self, root: &Path, revision: &Revision) -> Result<IndexStats> {
+        let start = Instant::now();
+        let files_indexed = AtomicUsize::new(0);
+        let total_lines = AtomicUsize::new(0);
+        let skipped: Mutex<BTreeMap<SkipReason, usize>> = Mutex::new(BTreeMap::new());
 
-            if !entry_path.is_file() {
-                continue;
-            }
+        let classifier = FileClassifier::for_root(root)?;
+        let mut writer = self.writer(resolve_threads(self.threads))?;
 
-            let language = match entry_path.extension().and_then(|e| e.to_str()) {
-                Some("rs") => "rust",
-                Some("py") => "python",
-                Some("ts" | "tsx") => "typescript",
-                Some("js" | "jsx") => "javascript",
-                Some("go") => "go",
-                Some("java") => "java",
-                Some("c" | "h") => "c",
-                Some("cpp" | "hpp" | "cc") => "cpp",
-                Some("rb") => "ruby",
-                Some("md") => "markdown",
-                Some("toml") => "toml",
-                Some("yaml" | "yml") => "yaml",
-                Some("json") => "json",
-                _ => continue,
+        git::for_each_blob(root, revision, self.threads, |relative_path, bytes| {
+            let Some(language) = lang::registry().detect(Path::new(relative_path)) else {
+                return Ok(());
             };
 
-            // Check if file needs re-indexing
-            let status = cache.check_file(entry_path);
-            if status == FileStatus::Unchanged {
-                skipped_files += 1;
-                continue;
-            }
-
-            let content = match fs::read_to_string(entry_path) {
-                Ok(c) => c,
-                Err(_) => continue,
+            let text = match classifier.check_bytes(relative_path, bytes) {
+                Ok(text) => text,
+                Err(reason) => {
+                    *skipped.lock().unwrap().entry(reason).or_insert(0) += 1;
+                    return Ok(());
+                }
             };
+            let line_count = text.content.lines().count();
 
-            let line_count = content.lines().count();
-            let relative_path = entry_path
-                .strip_prefix(path)
-                .unwrap_or(entry_path)
-                .to_string_lossy();
-
-            writer.add_document(doc!(
-                file_path_field => relative_path.to_string(),
-                content_field => content,
-                language_field => language,
-                line_count_field => line_count as u64
-            ))?;
-
-            // Update cache with new timestamp
-            cache.update_file(entry_path);
-            changed_files += 1;
-            stats.files_indexed += 1;
-            stats.total_lines += line_count;
-        }
+            self.add_file_documents(&writer, relative_path, relative_path, text, language)?;
+
+            files_indexed.fetch_add(1, Ordering::Relaxed);
+            total_lines.fetch_add(line_count, Ordering::Relaxed);
+            Ok(())
+        })?;
 
         writer.commit()?;
-        cache.save()?;
-        stats.duration_secs = start.elapsed().as_secs_f64();
 
-        tracing::info!(
-            "Incremental index: {} changed, {} unchanged",
-            changed_files,
-            skipped_files
-        );
+        Ok(IndexStats {
+            files_indexed: files_indexed.into_inner(),
+            total_lines: total_lines.into_inner(),
+            duration_secs: start.elapsed().as_secs_f64(),
+            skipped: skipped.into_inner().unwrap(),
+            ..Default::default()
+        })
+    }
 
-        Ok(stats)
+    /// Index the members of an archive or the cells of a notebook, as
+    /// `<archive>!/<member>` or `<notebook>#cell-<n>`
+    ///
+    /// Returns the number of members or cells and lines indexed, or None if
+    /// the container can't be read; it is then logged, counted as unreadable
+    /// and has no documents left in the index, rather than staying half
+    /// indexed. Errors are index writer errors.
+    fn add_container_documents(
+        &self,
+        writer: &IndexWriter,
+        classifier: &FileClassifier,
+        root: &Root,
+        path: &Path,
+        skipped: &Mutex<BTreeMap<SkipReason, usize>>,
+    ) -> Result<Option<(usize, usize)>> {
+        let container_path = root.workspace_path(path);
+        let mut files = 0;
+        let mut lines = 0;
+
+        let result = candidate::for_each_entry(classifier, root, path, |entry| {
+            let text = match entry.text {
+                Ok(text) => text,
+                Err(reason) => {
+                    *skipped.lock().unwrap().entry(reason).or_insert(0) += 1;
+                    return Ok(());
+                }
+            };
+            files += 1;
+            lines += text.content.lines().count();
+            self.add_file_documents(writer, &entry.path, &container_path, text, entry.language)
+        })?;
+        if let Err(e) = result {
+            tracing::warn!("Skipping unreadable {}: {:#}", container_path, e);
+            *skipped.lock().unwrap().entry(SkipReason::Unreadable).or_insert(0) += 1;
+            writer.delete_term(Term::from_field_text(self.fields.source_path, &container_path));
+            return Ok(None);
+        }
+
+        Ok(Some((files, lines)))
     }
 
-    /// Search the index for matching documents
-    pub fn search(&self, query_str: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let reader = self
-            .reader
-            .as_ref()
-            .context("Index not opened for reading")?;
-        let searcher = reader.searcher();
+    /// Open an index writer sized for `threads` indexing threads
+    fn writer(&self, threads: usize) -> Result<IndexWriter> {
+        // Tantivy caps useful writer threads at 8 and needs >= 15MB heap per thread
+        let writer_threads = threads.clamp(1, 8);
+        Ok(self
+            .index
+            .writer_with_num_threads(writer_threads, writer_threads * WRITER_HEAP_PER_THREAD)?)
+    }
+
+    /// Add a file's document and its symbol definitions, plus one document
+    /// per chunk when chunking is enabled
+    ///
+    /// `source_path` is the file on disk the documents come from; it differs
+    /// from `file_path` only for archive members.
+    fn add_file_documents(
+        &self,
+        writer: &IndexWriter,
+        file_path: &str,
+        source_path: &str,
+        text: SourceText,
+        language: &str,
+    ) -> Result<()> {
+        let f = &self.fields;
+        let encoding = text.encoding_name();
+        let content = text.content;
+
+        self.add_symbol_documents(writer, file_path, source_path, &content, language)?;
+
+        if self.index_chunks {
+            let chunks = self
+                .chunker
+                .chunk_file(Path::new(file_path), &content, Language::from_name(language))
+                .unwrap_or_default();
+
+            for chunk in chunks {
+                let mut chunk_doc = doc!(
+                    f.file_path => file_path,
+                    f.source_path => source_path,
+                    f.content => chunk.content,
+                    f.language => language,
+                    f.line_count => (chunk.end_line - chunk.start_line + 1) as u64,
+                    f.doc_type => DOC_TYPE_CHUNK,
+                    f.chunk_type => chunk.chunk_type.to_string(),
+                    f.start_line => chunk.start_line as u64,
+                    f.end_line => chunk.end_line as u64
+                );
+                if let Some(name) = chunk.name {
+                    chunk_doc.add_text(f.symbol, name);
+                }
+                self.tag_revision(&mut chunk_doc);
+                writer.add_document(chunk_doc)?;
+            }
+        }
+
+        let line_count = content.lines().count();
+        let mut file_doc = doc!(
+            f.file_path => file_path,
+            f.source_path => source_path,
+            f.content => content,
+            f.language => language,
+            f.line_count => line_count as u64,
+            f.doc_type => DOC_TYPE_FILE
+        );
+        if let Some(encoding) = encoding {
+            file_doc.add_text(f.encoding, encoding);
+        }
+        self.tag_revision(&mut file_doc);
+        writer.add_document(file_doc)?;
 
-        let file_path_field = self.schema.get_field("file_path").unwrap();
-        let content_field = self.schema.get_field("content").unwrap();
-        let language_field = self.schema.get_field("language").unwrap();
+        Ok(())
+    }
 
-        // Create query parser for content field
-        let query_parser =
-            QueryParser::for_index(&self.index, vec![content_field, file_path_field]);
-        let query = query_parser.parse_query(query_str)?;
+    /// Tag a document with the snapshot's revision, if any
+    fn tag_revision(&self, doc: &mut TantivyDocument) {
+        if let Some(revision) = &self.revision {
+            doc.add_text(self.fields.revision, revision);
+        }
+    }
 
-        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+    /// Search the index for matching documents
+    pub fn search(
+        &self,
+        query_str: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>> {
+        let f = &self.fields;
+        let hits = self.collect_hits(
+            query_str,
+            DOC_TYPE_FILE,
+            vec![f.content, f.file_path],
+            limit,
+            filter,
+        )?;
 
         let mut results = Vec::new();
-        for (score, doc_address) in top_docs {
-            let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
-
-            let file_path = retrieved_doc
-                .get_first(file_path_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            let content = retrieved_doc
-                .get_first(content_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            let language = retrieved_doc
-                .get_first(language_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
+        for (score, retrieved_doc) in hits {
+            let content = get_text(&retrieved_doc, f.content);
 
             // Find matching lines
             let matching_lines = find_matching_lines(&content, query_str);
 
+            let revision = get_text(&retrieved_doc, f.revision);
+            let encoding = get_text(&retrieved_doc, f.encoding);
             results.push(SearchResult {
-                file_path,
-                language,
+                file_path: get_text(&retrieved_doc, f.file_path),
+                language: get_text(&retrieved_doc, f.language),
                 score,
                 content,
                 matching_lines,
+                match_spans: Vec::new(),
+                revision: (!revision.is_empty()).then_some(revision),
+                encoding: (!encoding.is_empty()).then_some(encoding),
             });
         }
 
         Ok(results)
     }
+
+    /// Search chunk-level documents (requires an index built with chunks)
+    pub fn search_chunks(
+        &self,
+        query_str: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ChunkSearchResult>> {
+        let f = &self.fields;
+        let hits = self.collect_hits(
+            query_str,
+            DOC_TYPE_CHUNK,
+            vec![f.content, f.symbol, f.file_path],
+            limit,
+            filter,
+        )?;
+
+        Ok(hits
+            .into_iter()
+            .map(|(score, retrieved_doc)| {
+                let name = get_text(&retrieved_doc, f.symbol);
+                let start_line = get_u64(&retrieved_doc, f.start_line) as usize;
+                let content = get_text(&retrieved_doc, f.content);
+
+                // Matching lines, numbered within the file rather than the chunk
+                let matching_lines = find_matching_lines(&content, query_str)
+                    .into_iter()
+                    .map(|(line, text)| (line + start_line.saturating_sub(1), text))
+                    .collect();
+
+                ChunkSearchResult {
+                    file_path: get_text(&retrieved_doc, f.file_path),
+                    name: (!name.is_empty()).then_some(name),
+                    start_line,
+                    end_line: get_u64(&retrieved_doc, f.end_line) as usize,
+                    score,
+                    matching_lines,
+                }
+            })
+            .collect())
+    }
+
+    /// Run a query over one document type, applying the search filter
+    fn collect_hits(
+        &self,
+        query_str: &str,
+        doc_type: &str,
+        default_fields: Vec<Field>,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, TantivyDocument)>> {
+        let reader = self
+            .reader
+            .as_ref()
+            .context("Index not opened for reading")?;
+        let searcher = reader.searcher();
+
+        // Create query parser (identifiers stay whole at query time)
+        let query_parser = QueryParser::new(
+            self.schema.clone(),
+            default_fields,
+            tokenizer::query_tokenizers(),
+        );
+        let doc_type_query: Box<dyn Query> = Box::new(TermQuery::new(
+            Term::from_field_text(self.fields.doc_type, doc_type),
+            IndexRecordOption::Basic,
+        ));
+        let query: Box<dyn Query> = Box::new(BooleanQuery::new(vec![
+            (Occur::Must, query_parser.parse_query(query_str)?),
+            (Occur::Must, doc_type_query),
+        ]));
+        let query = with_language_filter(query, self.fields.language, filter);
+
+        // Path globs can't be expressed in the index, so page through hits until enough match
+        let page_size = if filter.has_path_globs() {
+            limit.max(1) * 4
+        } else {
+            limit.max(1)
+        };
+        let mut hits = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = searcher.search(&query, &TopDocs::with_limit(page_size).and_offset(offset))?;
+            let page_len = page.len();
+
+            for (score, doc_address) in page {
+                let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
+                let file_path = get_text(&retrieved_doc, self.fields.file_path);
+
+                if filter.matches_path(&file_path) && hits.len() < limit {
+                    hits.push((score, retrieved_doc));
+                }
+            }
+
+            if hits.len() >= limit || page_len < page_size {
+                break;
+            }
+            offset += page_size;
+        }
+
+        Ok(hits)
+    }
+}
+
+/// Problems with the Tantivy index at `index_path`; empty when it is healthy
+///
+/// Checks the format version, that every segment file passes its checksum
+/// and that the segments can be opened for searching.
+pub fn verify_index(index_path: &Path, expected_version: u32) -> Vec<String> {
+    if let Some(found) = stale_version(index_path, expected_version) {
+        return vec![format!(
+            "built by another seekr version (format v{}, expected v{})",
+            found, expected_version
+        )];
+    }
+    let index = match Index::open_in_dir(index_path) {
+        Ok(index) => index,
+        Err(e) => return vec![format!("cannot be opened: {}", e)],
+    };
+
+    let mut problems = Vec::new();
+    match index.validate_checksum() {
+        Ok(corrupted) if corrupted.is_empty() => {}
+        Ok(corrupted) => problems.push(format!(
+            "{} segment file(s) fail their checksum",
+            corrupted.len()
+        )),
+        Err(e) => problems.push(format!("segment files cannot be read: {}", e)),
+    }
+    if let Err(e) = index.reader() {
+        problems.push(format!("segments cannot be opened: {}", e));
+    }
+    problems
+}
+
+/// Whether any document has `value` in a raw string field
+fn has_documents(index: &Index, field: Field, value: &str) -> bool {
+    let query = TermQuery::new(Term::from_field_text(field, value), IndexRecordOption::Basic);
+
+    index
+        .reader()
+        .and_then(|reader| reader.searcher().search(&query, &Count))
+        .is_ok_and(|count| count > 0)
+}
+
+/// Read a stored text field ("" when absent)
+fn get_text(doc: &TantivyDocument, field: Field) -> String {
+    doc.get_first(field)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Read a stored u64 field (0 when absent)
+fn get_u64(doc: &TantivyDocument, field: Field) -> u64 {
+    doc.get_first(field).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+/// Restrict a query to the filter's languages (no-op without `--lang`)
+fn with_language_filter(
+    query: Box<dyn Query>,
+    language_field: Field,
+    filter: &SearchFilter,
+) -> Box<dyn Query> {
+    if filter.languages().is_empty() {
+        return query;
+    }
+
+    let languages: Vec<(Occur, Box<dyn Query>)> = filter
+        .languages()
+        .iter()
+        .map(|lang| {
+            let term = Term::from_field_text(language_field, lang);
+            let term_query: Box<dyn Query> =
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+            (Occur::Should, term_query)
+        })
+        .collect();
+
+    Box::new(BooleanQuery::new(vec![
+        (Occur::Must, query),
+        (Occur::Must, Box::new(BooleanQuery::new(languages))),
+    ]))
 }
 
 /// Find lines in content that match the query terms
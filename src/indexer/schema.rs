@@ -2,12 +2,28 @@
 //!
 //! Fields:
 //! - file_path: Stored + indexed (for path-based search)
+//! - source_path: Raw + indexed (document identity for delete-by-term)
 //! - content: Indexed + stored (main search target)
-//! - language: Stored + fast (for filtering)
+//! - language: Raw + indexed + stored + fast (for filtering)
 //! - line_count: Stored (for stats)
+//! - doc_type: Raw + indexed ("file", "chunk" or "symbol")
+//! - chunk_type, symbol, start_line, end_line: Chunk and symbol documents
+//! - symbol_name, container: Symbol documents only (definitions for `seekr def`)
+//! - revision: Commit id, on documents of a git revision snapshot (`--rev`)
+//! - encoding: Original encoding, on file documents transcoded to UTF-8
+//!
+//! Every index directory records the format version it was built with, so an
+//! index from another seekr release is rebuilt instead of half-working.
 
+use std::fs;
+use std::path::Path;
 use tantivy::schema::*;
 
+use serde::Serialize;
+
+use super::tokenizer::{CODE_TOKENIZER, SYMBOL_TOKENIZER};
+use crate::lock::write_atomic;
+
 /// A search result from the index
 #[derive(Debug, Clone)]
 pub struct SearchResult {
@@ -16,6 +32,99 @@ pub struct SearchResult {
     pub score: f32,
     pub content: String,
     pub matching_lines: Vec<(usize, String)>, // (line_number, line_content)
+    /// Byte ranges of the matches within each matching line, for regex
+    /// search; empty otherwise
+    pub match_spans: Vec<Vec<(usize, usize)>>,
+    /// Commit id when searching a revision snapshot
+    pub revision: Option<String>,
+    /// Encoding of the file when it is not UTF-8 (e.g. "windows-1252")
+    pub encoding: Option<String>,
+}
+
+/// A chunk-level search result (a function, class or block of a file)
+#[derive(Debug, Clone)]
+pub struct ChunkSearchResult {
+    pub file_path: String,
+    pub name: Option<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f32,
+    pub matching_lines: Vec<(usize, String)>, // (line_number, line_content)
+}
+
+/// A symbol definition found by `seekr def`
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolResult {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub language: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub container: Option<String>,
+}
+
+/// Format version of the code index; bump whenever fields or tokenization change
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// File in an index directory recording its format version
+const VERSION_FILE: &str = "seekr_version";
+
+/// Document type for whole files
+pub const DOC_TYPE_FILE: &str = "file";
+/// Document type for chunker output (functions, classes, blocks)
+pub const DOC_TYPE_CHUNK: &str = "chunk";
+/// Document type for symbol definitions (always indexed)
+pub const DOC_TYPE_SYMBOL: &str = "symbol";
+
+/// Resolved schema fields, looked up once per indexer
+#[derive(Debug, Clone, Copy)]
+pub struct Fields {
+    pub file_path: Field,
+    pub source_path: Field,
+    pub content: Field,
+    pub language: Field,
+    pub line_count: Field,
+    pub doc_type: Field,
+    pub chunk_type: Field,
+    pub symbol: Field,
+    pub start_line: Field,
+    pub end_line: Field,
+    pub symbol_name: Field,
+    pub container: Field,
+    pub revision: Field,
+    pub encoding: Field,
+}
+
+impl Fields {
+    /// Resolve fields, failing if the index was built with an older schema
+    pub fn new(schema: &Schema) -> anyhow::Result<Self> {
+        let field = |name: &str| {
+            schema.get_field(name).map_err(|_| {
+                anyhow::anyhow!(
+                    "Index is missing the `{}` field (built by an older seekr). Run `seekr index --force` to rebuild it.",
+                    name
+                )
+            })
+        };
+
+        Ok(Self {
+            file_path: field("file_path")?,
+            source_path: field("source_path")?,
+            content: field("content")?,
+            language: field("language")?,
+            line_count: field("line_count")?,
+            doc_type: field("doc_type")?,
+            chunk_type: field("chunk_type")?,
+            symbol: field("symbol")?,
+            start_line: field("start_line")?,
+            end_line: field("end_line")?,
+            symbol_name: field("symbol_name")?,
+            container: field("container")?,
+            revision: field("revision")?,
+            encoding: field("encoding")?,
+        })
+    }
 }
 
 /// Build the Tantivy schema for code indexing
@@ -28,30 +137,92 @@ pub fn build_schema() -> Schema {
         TextOptions::default()
             .set_indexing_options(
                 TextFieldIndexing::default()
-                    .set_tokenizer("default")
+                    .set_tokenizer(CODE_TOKENIZER)
                     .set_index_option(IndexRecordOption::WithFreqsAndPositions),
             )
             .set_stored(),
     );
 
+    // Source path - untokenized so a file's documents can be deleted by term
+    schema_builder.add_text_field("source_path", STRING | STORED);
+
     // Content - main search field
-    // Using default tokenizer which handles code reasonably well
+    // Code tokenizer splits camelCase/snake_case while keeping whole identifiers
     schema_builder.add_text_field(
         "content",
         TextOptions::default()
             .set_indexing_options(
                 TextFieldIndexing::default()
-                    .set_tokenizer("default")
+                    .set_tokenizer(CODE_TOKENIZER)
                     .set_index_option(IndexRecordOption::WithFreqsAndPositions),
             )
             .set_stored(),
     );
 
-    // Language - stored and fast field for filtering
-    schema_builder.add_text_field("language", TextOptions::default().set_stored());
+    // Language - indexed as a raw string and fast field for filtering
+    schema_builder.add_text_field("language", STRING | STORED | FAST);
 
     // Line count - stored for statistics
     schema_builder.add_u64_field("line_count", STORED);
 
+    // Document type - whole file or chunk, for restricting searches
+    schema_builder.add_text_field("doc_type", STRING | STORED);
+
+    // Chunk metadata - kind, symbol name and line span of a chunk document
+    schema_builder.add_text_field("chunk_type", STRING | STORED);
+    schema_builder.add_text_field(
+        "symbol",
+        TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(CODE_TOKENIZER)
+                    .set_index_option(IndexRecordOption::WithFreqs),
+            )
+            .set_stored(),
+    );
+    schema_builder.add_u64_field("start_line", STORED);
+    schema_builder.add_u64_field("end_line", STORED);
+
+    // Symbol definitions - the whole name and the enclosing type, each as one lowercased term
+    let symbol_indexing = TextFieldIndexing::default()
+        .set_tokenizer(SYMBOL_TOKENIZER)
+        .set_index_option(IndexRecordOption::Basic);
+    schema_builder.add_text_field(
+        "symbol_name",
+        TextOptions::default().set_indexing_options(symbol_indexing.clone()),
+    );
+    schema_builder.add_text_field(
+        "container",
+        TextOptions::default()
+            .set_indexing_options(symbol_indexing)
+            .set_stored(),
+    );
+
+    // Git revision - set on every document of a `--rev` snapshot index
+    schema_builder.add_text_field("revision", STRING | STORED);
+
+    // Encoding - set on file documents of files that were not UTF-8
+    schema_builder.add_text_field("encoding", STRING | STORED);
+
     schema_builder.build()
 }
+
+/// Format version of the index at `index_path`, if it exists and is not `expected`
+///
+/// Indexes built before versioning have no version file and report version 0.
+pub fn stale_version(index_path: &Path, expected: u32) -> Option<u32> {
+    if !index_path.join("meta.json").exists() {
+        return None;
+    }
+
+    let found = fs::read_to_string(index_path.join(VERSION_FILE))
+        .ok()
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(0);
+    (found != expected).then_some(found)
+}
+
+/// Record the format version of the index at `index_path`
+pub fn write_version(index_path: &Path, version: u32) -> anyhow::Result<()> {
+    write_atomic(&index_path.join(VERSION_FILE), version.to_string())
+}
@@ -0,0 +1,243 @@
+//! Symbol definition lookup for `seekr def`
+//!
+//! Every indexed file contributes one `symbol` document per definition the
+//! chunker finds (functions, methods, types, modules). Names are indexed as a
+//! single lowercased term, so lookups are term-level:
+//! - exact: one term query
+//! - prefix: a regex query over the term dictionary
+//! - fuzzy: a Levenshtein automaton (1 edit for short names, 2 otherwise)
+//!
+//! Matches are ordered by how close the name is to the query rather than by
+//! BM25, which carries no useful signal for single-term names, so every
+//! match is ranked rather than a top-scoring few.
+
+use anyhow::{Context, Result};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{BooleanQuery, FuzzyTermQuery, Occur, Query, RegexQuery, TermQuery};
+use tantivy::schema::*;
+use tantivy::{doc, IndexWriter};
+
+use super::schema::{SymbolResult, DOC_TYPE_SYMBOL};
+use super::{get_text, get_u64, with_language_filter, Indexer};
+use crate::chunker::Language;
+use crate::filter::SearchFilter;
+
+/// How a symbol name is matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolMatch {
+    Exact,
+    Prefix,
+    Fuzzy,
+}
+
+impl Indexer {
+    /// Add one document per symbol definition in a file
+    pub(super) fn add_symbol_documents(
+        &self,
+        writer: &IndexWriter,
+        file_path: &str,
+        source_path: &str,
+        content: &str,
+        language: &str,
+    ) -> Result<()> {
+        let f = &self.fields;
+
+        for symbol in self
+            .chunker
+            .extract_symbols(content, Language::from_name(language))
+        {
+            let mut symbol_doc = doc!(
+                f.file_path => file_path,
+                f.source_path => source_path,
+                f.language => language,
+                f.doc_type => DOC_TYPE_SYMBOL,
+                f.chunk_type => symbol.kind.to_string(),
+                f.symbol => symbol.name.as_str(),
+                f.symbol_name => symbol.name.as_str(),
+                f.start_line => symbol.start_line as u64,
+                f.end_line => symbol.end_line as u64
+            );
+            if let Some(container) = symbol.container {
+                symbol_doc.add_text(f.container, container);
+            }
+            self.tag_revision(&mut symbol_doc);
+            writer.add_document(symbol_doc)?;
+        }
+
+        Ok(())
+    }
+
+    /// Find symbol definitions by name
+    ///
+    /// `name` may be qualified (`Indexer::search`, `Server.Run`); the last
+    /// segment is matched as the symbol and the one before it must equal
+    /// the enclosing container. `kind` restricts to one symbol kind.
+    pub fn find_symbols(
+        &self,
+        name: &str,
+        mode: SymbolMatch,
+        kind: Option<&str>,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SymbolResult>> {
+        let reader = self
+            .reader
+            .as_ref()
+            .context("Index not opened for reading")?;
+        let searcher = reader.searcher();
+        let f = &self.fields;
+
+        let (container, name) = split_qualified(name);
+        let needle = name.to_lowercase();
+        let term = Term::from_field_text(f.symbol_name, &needle);
+
+        let name_query: Box<dyn Query> = match mode {
+            SymbolMatch::Exact => Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+            SymbolMatch::Prefix => Box::new(
+                RegexQuery::from_pattern(&format!("{}.*", escape_regex(&needle)), f.symbol_name)
+                    .context("Invalid symbol prefix")?,
+            ),
+            SymbolMatch::Fuzzy => {
+                let distance = if needle.chars().count() <= 4 { 1 } else { 2 };
+                Box::new(FuzzyTermQuery::new(term, distance, true))
+            }
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![
+            (Occur::Must, name_query),
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(f.doc_type, DOC_TYPE_SYMBOL),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+        ];
+        if let Some(kind) = kind {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(f.chunk_type, &kind.to_lowercase()),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        if let Some(container) = container {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(f.container, &container.to_lowercase()),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        let query = with_language_filter(Box::new(BooleanQuery::new(clauses)), f.language, filter);
+
+        // Path globs can't be expressed in the index, so every match is checked
+        let mut results = Vec::new();
+        for doc_address in searcher.search(&query, &DocSetCollector)? {
+            let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
+            let file_path = get_text(&retrieved_doc, f.file_path);
+            if !filter.matches_path(&file_path) {
+                continue;
+            }
+            let symbol_container = get_text(&retrieved_doc, f.container);
+
+            results.push(SymbolResult {
+                name: get_text(&retrieved_doc, f.symbol),
+                kind: get_text(&retrieved_doc, f.chunk_type),
+                file_path,
+                language: get_text(&retrieved_doc, f.language),
+                start_line: get_u64(&retrieved_doc, f.start_line) as usize,
+                end_line: get_u64(&retrieved_doc, f.end_line) as usize,
+                container: (!symbol_container.is_empty()).then_some(symbol_container),
+            });
+        }
+
+        // Closest names first, then exact-case matches, then by location
+        results.sort_by_cached_key(|r| {
+            (
+                edit_distance(&r.name.to_lowercase(), &needle),
+                r.name.len(),
+                r.name != name,
+                r.file_path.clone(),
+                r.start_line,
+            )
+        });
+        results.truncate(limit);
+
+        Ok(results)
+    }
+}
+
+/// Split `Type::method` / `Type.method` into (container, name)
+fn split_qualified(name: &str) -> (Option<&str>, &str) {
+    let split = name
+        .rfind("::")
+        .map(|i| (i, 2))
+        .or_else(|| name.rfind('.').map(|i| (i, 1)));
+
+    match split {
+        Some((i, len)) if i > 0 && i + len < name.len() => {
+            let qualifier = &name[..i];
+            let container = qualifier
+                .rsplit([':', '.'])
+                .next()
+                .unwrap_or(qualifier);
+            (Some(container), &name[i + len..])
+        }
+        _ => (None, name),
+    }
+}
+
+/// Escape regex metacharacters so a name matches literally
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Levenshtein distance between two strings (by chars)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_qualified() {
+        assert_eq!(split_qualified("search"), (None, "search"));
+        assert_eq!(split_qualified("Indexer::search"), (Some("Indexer"), "search"));
+        assert_eq!(split_qualified("crate::indexer::Indexer::new"), (Some("Indexer"), "new"));
+        assert_eq!(split_qualified("Server.Run"), (Some("Server"), "Run"));
+        assert_eq!(split_qualified("::new"), (None, "::new"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("search", "search"), 0);
+        assert_eq!(edit_distance("serach", "search"), 2);
+        assert_eq!(edit_distance("index", "indexer"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,301 @@
+//! Code-aware tokenizer for the content and path fields
+//!
+//! Source code identifiers pack several words into one token (`getUserById`,
+//! `parse_http_header`, `Foo::bar`). The default tokenizer keeps them opaque,
+//! so searching `user id` never finds `getUserById`.
+//!
+//! At index time every identifier is emitted whole *and* split into
+//! sub-words on camelCase / PascalCase humps, `_` and letter/digit
+//! boundaries. Qualified paths joined by `::` or `.` are additionally
+//! emitted as one compound token. At query time identifiers are kept whole,
+//! so `getUserById` is an exact term lookup while `user id` matches the
+//! sub-words. All tokens are lowercased.
+//!
+//! Symbol names for `seekr def` use a separate tokenizer that keeps the
+//! whole name as one lowercased term, for exact, prefix and fuzzy lookups.
+
+use tantivy::tokenizer::{
+    LowerCaser, RawTokenizer, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
+    TokenizerManager,
+};
+use tantivy::Index;
+
+/// Name the code tokenizer is registered under
+pub const CODE_TOKENIZER: &str = "code";
+
+/// Name the whole-name symbol tokenizer is registered under
+pub const SYMBOL_TOKENIZER: &str = "symbol";
+
+/// Tokens longer than this (in bytes) are dropped (base64 blobs, hashes)
+pub(super) const MAX_TOKEN_LEN: usize = 128;
+
+/// Register the index-time code and symbol tokenizers on an index
+pub fn register_tokenizers(index: &Index) {
+    index
+        .tokenizers()
+        .register(CODE_TOKENIZER, analyzer(CodeTokenizer::for_indexing()));
+    index.tokenizers().register(
+        SYMBOL_TOKENIZER,
+        TextAnalyzer::builder(RawTokenizer::default())
+            .filter(LowerCaser)
+            .build(),
+    );
+}
+
+/// Tokenizer manager for query parsing, which keeps identifiers whole
+pub fn query_tokenizers() -> TokenizerManager {
+    let manager = TokenizerManager::default();
+    manager.register(CODE_TOKENIZER, analyzer(CodeTokenizer::for_query()));
+    manager
+}
+
+fn analyzer(tokenizer: CodeTokenizer) -> TextAnalyzer {
+    TextAnalyzer::builder(tokenizer)
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .build()
+}
+
+/// Tokenizer that understands identifier conventions in source code
+#[derive(Clone, Debug)]
+pub struct CodeTokenizer {
+    /// Emit sub-words of identifiers (index time) or only whole identifiers (query time)
+    split_subwords: bool,
+}
+
+impl CodeTokenizer {
+    pub fn for_indexing() -> Self {
+        Self {
+            split_subwords: true,
+        }
+    }
+
+    pub fn for_query() -> Self {
+        Self {
+            split_subwords: false,
+        }
+    }
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CodeTokenStream {
+        let mut tokens = Vec::new();
+        let mut position = 0;
+
+        for chain in qualified_chains(text) {
+            let first = chain[0];
+            let last = chain[chain.len() - 1];
+
+            // Whole qualified path (`foo::bar`, `self.config`)
+            if chain.len() > 1 {
+                push_token(&mut tokens, text, first.0, last.1, position);
+                if !self.split_subwords {
+                    position += 1;
+                    continue;
+                }
+            }
+
+            for &(start, end) in &chain {
+                let ident = &text[start..end];
+                let subwords = split_identifier(ident);
+
+                if !self.split_subwords || subwords.len() != 1 || subwords[0] != (0, ident.len())
+                {
+                    push_token(&mut tokens, text, start, end, position);
+                }
+
+                if self.split_subwords {
+                    for (sub_start, sub_end) in subwords {
+                        push_token(&mut tokens, text, start + sub_start, start + sub_end, position);
+                        position += 1;
+                    }
+                } else {
+                    position += 1;
+                }
+            }
+        }
+
+        CodeTokenStream {
+            tokens,
+            index: None,
+        }
+    }
+}
+
+/// Token stream over tokens computed up front
+pub struct CodeTokenStream {
+    tokens: Vec<Token>,
+    index: Option<usize>,
+}
+
+impl TokenStream for CodeTokenStream {
+    fn advance(&mut self) -> bool {
+        let next = self.index.map_or(0, |i| i + 1);
+        self.index = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index.unwrap_or(0)]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index.unwrap_or(0)]
+    }
+}
+
+fn push_token(tokens: &mut Vec<Token>, text: &str, start: usize, end: usize, position: usize) {
+    tokens.push(Token {
+        offset_from: start,
+        offset_to: end,
+        position,
+        text: text[start..end].to_lowercase(),
+        position_length: 1,
+    });
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Group identifiers joined by `::` or `.` into chains of byte ranges
+fn qualified_chains(text: &str) -> Vec<Vec<(usize, usize)>> {
+    let mut chains: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut current: Vec<(usize, usize)> = Vec::new();
+    let mut ident_start: Option<usize> = None;
+    // Byte offset where a pending separator ended, if the chain may continue
+    let mut continues_at: Option<usize> = None;
+
+    let mut iter = text.char_indices().peekable();
+    while let Some((i, c)) = iter.next() {
+        if is_ident_char(c) {
+            if ident_start.is_none() {
+                if continues_at.take() != Some(i) && !current.is_empty() {
+                    chains.push(std::mem::take(&mut current));
+                }
+                ident_start = Some(i);
+            }
+            continue;
+        }
+
+        if let Some(start) = ident_start.take() {
+            current.push((start, i));
+        }
+
+        let separator_end = match c {
+            '.' => Some(i + 1),
+            ':' if matches!(iter.peek(), Some((_, ':'))) => {
+                iter.next();
+                Some(i + 2)
+            }
+            _ => None,
+        };
+
+        match separator_end {
+            Some(end) if continues_at.is_none() && text[..i].ends_with(is_ident_char) => {
+                continues_at = Some(end);
+            }
+            _ => {
+                continues_at = None;
+                if !current.is_empty() {
+                    chains.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+
+    if let Some(start) = ident_start {
+        current.push((start, text.len()));
+    }
+    if !current.is_empty() {
+        chains.push(current);
+    }
+
+    chains
+}
+
+/// Split an identifier into sub-word byte ranges
+///
+/// `getUserById` → get, User, By, Id; `HTTPServer` → HTTP, Server;
+/// `parse_v2_header` → parse, v, 2, header.
+pub(super) fn split_identifier(ident: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = ident.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (idx, &(i, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(s) = start.take() {
+                parts.push((s, i));
+            }
+            continue;
+        }
+
+        if let Some(s) = start {
+            let prev = chars[idx - 1].1;
+            let next = chars.get(idx + 1).map(|&(_, n)| n);
+
+            let boundary = (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_alphabetic() && c.is_numeric())
+                || (prev.is_numeric() && c.is_alphabetic())
+                || (prev.is_uppercase()
+                    && c.is_uppercase()
+                    && next.is_some_and(|n| n.is_lowercase()));
+
+            if boundary {
+                parts.push((s, i));
+                start = Some(i);
+            }
+        } else {
+            start = Some(i);
+        }
+    }
+
+    if let Some(s) = start {
+        parts.push((s, ident.len()));
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(mut tokenizer: CodeTokenizer, text: &str) -> Vec<String> {
+        let mut stream = tokenizer.token_stream(text);
+        let mut out = Vec::new();
+        while stream.advance() {
+            out.push(stream.token().text.clone());
+        }
+        out
+    }
+
+    #[test]
+    fn test_camel_case_keeps_whole_identifier() {
+        let toks = tokens(CodeTokenizer::for_indexing(), "getUserById(id)");
+        assert_eq!(toks, ["getuserbyid", "get", "user", "by", "id", "id"]);
+    }
+
+    #[test]
+    fn test_snake_case_and_digits() {
+        let toks = tokens(CodeTokenizer::for_indexing(), "parse_v2_header HTTPServer");
+        assert_eq!(
+            toks,
+            ["parse_v2_header", "parse", "v", "2", "header", "httpserver", "http", "server"]
+        );
+    }
+
+    #[test]
+    fn test_qualified_paths() {
+        let toks = tokens(CodeTokenizer::for_indexing(), "Foo::bar + self.a.b");
+        assert_eq!(toks, ["foo::bar", "foo", "bar", "self.a.b", "self", "a", "b"]);
+    }
+
+    #[test]
+    fn test_query_keeps_identifiers_whole() {
+        let toks = tokens(CodeTokenizer::for_query(), "getUserById Foo::bar user");
+        assert_eq!(toks, ["getuserbyid", "foo::bar", "user"]);
+    }
+}
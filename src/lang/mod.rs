@@ -0,0 +1,170 @@
+//! Language registry: which files are indexed and as what language
+//!
+//! Architecture Decision: One registry maps file extensions and exact file
+//! names to language names, shared by the indexer, watcher, chunker and
+//! output highlighter so they can never disagree. The built-in table can be
+//! extended or overridden from config:
+//!
+//! ```text
+//! seekr config "lang.*.mts" typescript   # extension
+//! seekr config lang.BUILD starlark       # exact file name
+//! seekr config "lang.*.json" none        # stop indexing an extension
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+
+/// Language value that removes a mapping
+pub const NO_LANGUAGE: &str = "none";
+
+/// Built-in languages and their extensions (the first is used for highlighting)
+const BUILTIN: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("python", &["py"]),
+    ("typescript", &["ts", "tsx"]),
+    ("javascript", &["js", "jsx", "mjs", "cjs"]),
+    ("go", &["go"]),
+    ("java", &["java"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cpp", "hpp", "cc"]),
+    ("ruby", &["rb"]),
+    ("markdown", &["md"]),
+    ("toml", &["toml"]),
+    ("yaml", &["yaml", "yml"]),
+    ("json", &["json"]),
+];
+
+/// Maps paths to language names
+#[derive(Debug, Clone, Default)]
+pub struct LanguageRegistry {
+    /// Extension (without the dot) -> language
+    extensions: HashMap<String, String>,
+    /// Exact file name -> language
+    file_names: HashMap<String, String>,
+    /// Language -> extension used to pick a syntax highlighter
+    primary_extensions: HashMap<String, String>,
+}
+
+impl LanguageRegistry {
+    /// The built-in table only
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for (language, extensions) in BUILTIN {
+            for extension in *extensions {
+                registry.add(&format!("*.{}", extension), language);
+            }
+        }
+        registry
+    }
+
+    /// The built-in table plus config overrides (`*.ext` or exact file name -> language)
+    pub fn with_overrides(overrides: &BTreeMap<String, String>) -> Self {
+        let mut registry = Self::builtin();
+        for (pattern, language) in overrides {
+            registry.add(pattern, language);
+        }
+        registry
+    }
+
+    /// Map a pattern (`*.ext` or an exact file name) to a language, or unmap it with "none"
+    fn add(&mut self, pattern: &str, language: &str) {
+        let (map, key) = match pattern.strip_prefix("*.") {
+            Some(extension) => (&mut self.extensions, extension.to_lowercase()),
+            None => (&mut self.file_names, pattern.to_string()),
+        };
+
+        if language == NO_LANGUAGE {
+            map.remove(&key);
+            return;
+        }
+
+        let language = language.to_lowercase();
+        if pattern.starts_with("*.") {
+            self.primary_extensions
+                .entry(language.clone())
+                .or_insert_with(|| key.clone());
+        }
+        map.insert(key, language);
+    }
+
+    /// Language of a file, or None if it should not be indexed
+    pub fn detect(&self, path: &Path) -> Option<&str> {
+        let file_name = path.file_name()?.to_str()?;
+        if let Some(language) = self.file_names.get(file_name) {
+            return Some(language);
+        }
+
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        self.extensions.get(&extension).map(String::as_str)
+    }
+
+    /// Extension to look up a syntax highlighter for a language
+    pub fn highlight_extension(&self, language: &str) -> Option<&str> {
+        self.primary_extensions.get(language).map(String::as_str)
+    }
+}
+
+/// Whether a config key pattern is valid (`*.ext` or a plain file name)
+pub fn is_valid_pattern(pattern: &str) -> bool {
+    let name = pattern.strip_prefix("*.").unwrap_or(pattern);
+    !name.is_empty() && !name.contains(['*', '/', '\\'])
+}
+
+/// The registry for this process: built-ins plus the user's config
+pub fn registry() -> &'static LanguageRegistry {
+    static REGISTRY: OnceLock<LanguageRegistry> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| match Config::load() {
+        Ok(config) => LanguageRegistry::with_overrides(&config.languages),
+        Err(e) => {
+            tracing::warn!("Ignoring language overrides: {}", e);
+            LanguageRegistry::builtin()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_detection() {
+        let registry = LanguageRegistry::builtin();
+        assert_eq!(registry.detect(Path::new("src/main.rs")), Some("rust"));
+        assert_eq!(registry.detect(Path::new("web/App.TSX")), Some("typescript"));
+        assert_eq!(registry.detect(Path::new("Makefile")), None);
+        assert_eq!(registry.highlight_extension("javascript"), Some("js"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides: BTreeMap<String, String> = [
+            ("*.mts", "typescript"),
+            ("*.kt", "Kotlin"),
+            ("Dockerfile", "dockerfile"),
+            ("*.json", "none"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let registry = LanguageRegistry::with_overrides(&overrides);
+
+        assert_eq!(registry.detect(Path::new("lib/index.mts")), Some("typescript"));
+        assert_eq!(registry.detect(Path::new("App.kt")), Some("kotlin"));
+        assert_eq!(registry.detect(Path::new("docker/Dockerfile")), Some("dockerfile"));
+        assert_eq!(registry.detect(Path::new("package.json")), None);
+        assert_eq!(registry.highlight_extension("typescript"), Some("ts"));
+    }
+
+    #[test]
+    fn test_pattern_validation() {
+        assert!(is_valid_pattern("*.proto"));
+        assert!(is_valid_pattern("BUILD"));
+        assert!(!is_valid_pattern("*.*"));
+        assert!(!is_valid_pattern("src/*.rs"));
+        assert!(!is_valid_pattern("*."));
+    }
+}
@@ -0,0 +1,126 @@
+//! Coordination between seekr processes sharing `~/.seekr`
+//!
+//! `seekr index`, `seekr watch`, `seekr doctor --fix` and any number of
+//! searches can run at the same time against one workspace.
+//!
+//! Architecture Decision: Lock writers, never readers:
+//! 1. Writers hold the workspace lock (an advisory lock on `<data dir>/lock`)
+//!    while they touch any of its indexes. A second writer fails fast with
+//!    `IndexBusy` rather than interleaving its writes with the first.
+//! 2. Readers take no lock. Files seekr writes itself are replaced atomically
+//!    (`write_atomic`) and rebuilt indexes are swapped in whole (`StagedDir`),
+//!    so a reader sees either the old or the new version, never a mix.
+//! 3. The registry shared by all workspaces is updated under its own lock,
+//!    held only for the read-modify-write.
+
+use anyhow::{Context, Result};
+use fs4::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::workspace::Workspace;
+
+/// Lock file in a workspace data directory
+const LOCK_FILE: &str = "lock";
+
+/// Another process is writing to the workspace's indexes
+#[derive(Debug, thiserror::Error)]
+#[error("Index busy: {holder} is updating {}. Try again when it finishes.", root.display())]
+pub struct IndexBusy {
+    /// Who holds the lock, e.g. "seekr watch (pid 4242)"
+    pub holder: String,
+    pub root: PathBuf,
+}
+
+/// Exclusive right to write a workspace's indexes, released on drop
+#[derive(Debug)]
+pub struct WorkspaceLock {
+    file: File,
+}
+
+impl WorkspaceLock {
+    /// Take the writer lock of a workspace without waiting
+    ///
+    /// Fails with `IndexBusy` if another process holds it. `command` names
+    /// this process in the error other writers get (e.g. "seekr index").
+    pub fn acquire(workspace: &Workspace, command: &str) -> Result<Self> {
+        fs::create_dir_all(workspace.data_dir())?;
+        let path = workspace.data_dir().join(LOCK_FILE);
+        let mut file = open_lock_file(&path)?;
+
+        if let Err(e) = file.try_lock_exclusive() {
+            if e.raw_os_error() != fs4::lock_contended_error().raw_os_error() {
+                return Err(e).with_context(|| format!("Failed to lock {:?}", path));
+            }
+            let mut holder = String::new();
+            file.read_to_string(&mut holder).ok();
+            let holder = match holder.trim() {
+                "" => "another seekr process".to_string(),
+                holder => holder.to_string(),
+            };
+            return Err(IndexBusy {
+                holder,
+                root: workspace.root().to_path_buf(),
+            }
+            .into());
+        }
+
+        // Tell processes that find the lock taken who holds it
+        file.set_len(0)?;
+        write!(file, "{} (pid {})", command, std::process::id())?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Block until the lock file at `path` is held exclusively
+///
+/// For short critical sections; the lock is released when the returned file is dropped.
+pub fn wait_for(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = open_lock_file(path)?;
+    file.lock_exclusive()
+        .with_context(|| format!("Failed to lock {:?}", path))?;
+    Ok(file)
+}
+
+/// Replace a file's contents so concurrent readers see the old or the new
+/// version, never a partial write
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let tmp_path = tmp_path_for(path);
+    let mut file =
+        File::create(&tmp_path).with_context(|| format!("Failed to create {:?}", tmp_path))?;
+    file.write_all(contents.as_ref())?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {:?}", path))
+}
+
+/// Sibling temporary path for building a replacement of `path`
+pub fn tmp_path_for(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp{}", name, std::process::id()))
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file {:?}", path))
+}
@@ -9,26 +9,47 @@ use clap::Parser;
 use colored::Colorize;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+mod archive;
 mod cache;
 mod chunker;
+mod classify;
 mod cli;
+mod config;
+mod deps;
+mod doctor;
 mod embedder;
+mod encoding;
+mod filter;
+mod git;
+mod history;
 mod indexer;
+mod lang;
+mod lock;
+mod notebook;
 mod output;
 mod ranker;
 mod semantic;
+mod status;
 mod vector_store;
+mod walk;
 mod watcher;
+mod workspace;
 
-use cli::{Cli, Commands};
-use indexer::Indexer;
+use cli::{Cli, Commands, RootAction};
+use indexer::{Indexer, SymbolMatch};
+use lock::WorkspaceLock;
 use output::ResultPrinter;
+use workspace::{Workspace, WorkspaceRegistry};
 
 fn main() -> Result<()> {
-    // Initialize tracing
+    // Initialize tracing; logs go to stderr so `--json` output stays parseable
     tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_writer(std::io::stderr),
+        )
         .init();
 
     let cli = Cli::parse();
@@ -38,8 +59,27 @@ fn main() -> Result<()> {
             path,
             force,
             semantic,
+            chunks,
+            threads,
+            rev,
+            history,
+            deps,
+            dry_run,
         } => {
             let path = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let workspace = Workspace::for_root(&path)?;
+            let path = workspace.root().to_path_buf();
+            if dry_run {
+                return list_files(&workspace, threads, false, false);
+            }
+            let _lock = WorkspaceLock::acquire(&workspace, "seekr index")?;
+
+            if let Some(rev) = rev {
+                if semantic {
+                    println!("⚠️  Semantic indexing is not available for revisions; building the lexical index only.");
+                }
+                return index_revision(&workspace, &rev, force, chunks, threads);
+            }
             tracing::info!(
                 "Indexing: {:?} (force={}, semantic={})",
                 path,
@@ -47,83 +87,95 @@ fn main() -> Result<()> {
                 semantic
             );
 
-            // Load file cache for incremental indexing
-            let home = dirs::home_dir().expect("Could not find home directory");
-            let cache_path = home.join(".seekr");
-            let mut file_cache = cache::FileCache::load(&cache_path)?;
-
-            // BM25 lexical index
-            let mut indexer = Indexer::new(&path, force)?;
-            let stats = if force {
-                // Force = full reindex
-                file_cache.clear();
-                let s = indexer.index_directory(&path)?;
-                // Update cache for all files
-                for entry in ignore::WalkBuilder::new(&path)
-                    .hidden(true)
-                    .git_ignore(true)
-                    .build()
-                    .filter_map(|e| e.ok())
-                {
-                    if entry.path().is_file() {
-                        file_cache.update_file(entry.path());
-                    }
-                }
-                file_cache.save()?;
-                s
-            } else {
-                // Incremental = only changed files
-                indexer.index_directory_incremental(&path, &mut file_cache)?
-            };
+            // Keep chunk documents once a workspace has opted in
+            let chunks = chunks || Indexer::has_chunks_at(&workspace.index_path());
+
+            // BM25 lexical index; a full reindex is built aside and swapped in when done
+            let mut indexer = Indexer::new(&workspace, force)?
+                .with_chunks(chunks)
+                .with_threads(threads);
+            if let Some(old_version) = indexer.upgraded_from() {
+                println!(
+                    "\n🔄 Index format changed (v{} → v{}), rebuilding it from scratch...",
+                    old_version,
+                    indexer::SCHEMA_VERSION
+                );
+            }
+
+            // The file cache lives in the index, so a rebuild starts with an empty one
+            let mut file_cache = cache::FileCache::load(indexer.path())?;
+            let roots = workspace.roots()?;
+            let stats = indexer.index_directory_incremental(&roots, &mut file_cache)?;
+            indexer.publish()?;
+            workspace.register()?;
 
-            if force {
+            if force || indexer.upgraded_from().is_some() {
                 println!("\n✨ Lexical indexing complete! (full reindex)");
             } else {
                 println!("\n✨ Lexical indexing complete! (incremental)");
             }
             println!("   Files indexed: {}", stats.files_indexed);
+            if stats.files_deleted > 0 {
+                println!("   Files removed: {}", stats.files_deleted);
+            }
+            if !stats.skipped.is_empty() {
+                println!("   Files skipped: {}", format_skipped(&stats.skipped));
+            }
             println!("   Total lines: {}", stats.total_lines);
             println!("   Time: {:.2}s", stats.duration_secs);
 
-            // Semantic index (if requested)
+            // Deleted files must also leave the semantic index (unless it is rebuilt below)
+            if !stats.deleted_files.is_empty() && !semantic {
+                let mut semantic_indexer =
+                    semantic::SemanticIndexer::new(&workspace.semantic_path())?;
+                let purged = semantic_indexer.remove_files(&stats.deleted_files)?;
+                if purged > 0 {
+                    tracing::info!("Purged {} semantic chunks of deleted files", purged);
+                }
+            }
+
+            // Semantic index (if requested); every file is embedded, so it is always rebuilt aside
             if semantic {
                 println!("\n🧠 Building semantic index (this may take a while on first run)...");
 
-                let home = dirs::home_dir().expect("Could not find home directory");
-                let semantic_path = home.join(".seekr");
-                let mut semantic_indexer = semantic::SemanticIndexer::new(&semantic_path)?;
-
-                // Collect files for semantic indexing
-                let mut files: Vec<(std::path::PathBuf, String)> = Vec::new();
-                let walker = ignore::WalkBuilder::new(&path)
-                    .hidden(true)
-                    .git_ignore(true)
-                    .build();
-
-                for entry in walker.filter_map(|e| e.ok()) {
-                    let entry_path = entry.path();
-                    if entry_path.is_file() {
-                        if let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) {
-                            if matches!(ext, "rs" | "py" | "js" | "jsx" | "ts" | "tsx" | "go") {
-                                if let Ok(content) = std::fs::read_to_string(entry_path) {
-                                    files.push((entry_path.to_path_buf(), content));
-                                }
-                            }
-                        }
-                    }
-                }
-
-                let file_refs: Vec<(&std::path::Path, String)> = files
-                    .iter()
-                    .map(|(p, c)| (p.as_path(), c.clone()))
-                    .collect();
+                let staged = workspace.stage_semantic()?;
+                let mut semantic_indexer =
+                    semantic::SemanticIndexer::new(&staged.path())?.with_threads(threads);
 
-                let sem_stats = semantic_indexer.index_files(&file_refs)?;
+                let files = collect_semantic_files(&roots, threads)?;
+                let sem_stats = semantic_indexer.index_files(&files)?;
+                staged.publish()?;
 
                 println!("   Chunks created: {}", sem_stats.chunks_created);
                 println!("   Embeddings: {}", sem_stats.embeddings_generated);
                 println!("   Time: {:.2}s", sem_stats.duration_secs);
             }
+
+            // Commit history (if requested)
+            if history {
+                println!("\n📜 Indexing commit history...");
+
+                // A forced or upgrade rebuild is staged, so log-search keeps working meanwhile
+                let mut history_indexer = history::HistoryIndexer::create(&workspace, force)?;
+                if let Some(old_version) = history_indexer.upgraded_from() {
+                    println!(
+                        "   History index format changed (v{} → v{}), rebuilding it from scratch...",
+                        old_version,
+                        history::HISTORY_VERSION
+                    );
+                }
+                let history_stats = history_indexer.index_repository(&path)?;
+                history_indexer.publish()?;
+
+                println!("   Commits indexed: {}", history_stats.commits_indexed);
+                println!("   Hunks: {}", history_stats.hunks_indexed);
+                println!("   Time: {:.2}s", history_stats.duration_secs);
+            }
+
+            // Dependency sources (if requested)
+            if deps {
+                index_dependencies(&workspace, &roots, force, threads)?;
+            }
         }
         Commands::Search {
             query,
@@ -131,18 +183,68 @@ fn main() -> Result<()> {
             context,
             semantic,
             hybrid,
+            regex,
             alpha,
             json,
+            lang,
+            path,
+            exclude,
+            rev,
+            scope,
         } => {
             tracing::info!(
-                "Searching for: {} (semantic={}, hybrid={}, alpha={}, json={})",
+                "Searching for: {} (semantic={}, hybrid={}, regex={}, alpha={}, json={})",
                 query,
                 semantic,
                 hybrid,
+                regex,
                 alpha,
                 json
             );
 
+            let workspace = Workspace::discover(&std::env::current_dir()?)?;
+            let filter = filter::SearchFilter::new(&lang, &path, &exclude)?;
+
+            if scope != "workspace" && (semantic || hybrid) {
+                anyhow::bail!(
+                    "Dependency sources are only indexed lexically; drop --semantic/--hybrid to search --scope {}.",
+                    scope
+                );
+            }
+            let deps_path = workspace.deps_index_path();
+            if scope != "workspace" && !deps_path.exists() {
+                anyhow::bail!("No dependency index. Run `seekr index --deps` first.");
+            }
+
+            // Lexical search runs against the working tree or a revision snapshot
+            let index_path = match &rev {
+                Some(rev) => {
+                    let revision = git::resolve(workspace.root(), rev)?;
+                    let index_path = workspace.revision_index_path(&revision.sha);
+                    if !index_path.exists() {
+                        anyhow::bail!(
+                            "Revision {} ({}) is not indexed. Run `seekr index --rev {}` first.",
+                            rev,
+                            revision.short_sha(),
+                            rev
+                        );
+                    }
+                    if indexer::stale_version(&index_path, indexer::SCHEMA_VERSION).is_some() {
+                        anyhow::bail!(
+                            "Revision {} ({}) was indexed by another seekr version. Run `seekr index --rev {}` to rebuild it.",
+                            rev,
+                            revision.short_sha(),
+                            rev
+                        );
+                    }
+                    if !json {
+                        println!("\n📌 Searching {} ({})", rev, revision.short_sha());
+                    }
+                    index_path
+                }
+                None => workspace.index_path(),
+            };
+
             if hybrid {
                 // Hybrid search: combine BM25 + semantic
                 if !json {
@@ -150,49 +252,78 @@ fn main() -> Result<()> {
                 }
 
                 // Get BM25 results
-                let index_path = Indexer::default_index_path()?;
-                let indexer = Indexer::open(&index_path)?;
-                let bm25_results = indexer.search(&query, limit * 2)?;
+                let indexer = Indexer::open(&workspace.index_path())?;
 
                 // Get semantic results
-                let home = dirs::home_dir().expect("Could not find home directory");
-                let semantic_path = home.join(".seekr");
-                let mut semantic_indexer = semantic::SemanticIndexer::new(&semantic_path)?;
+                let mut semantic_indexer =
+                    semantic::SemanticIndexer::open(&workspace.semantic_path())?;
 
                 if !semantic_indexer.index_exists() {
                     println!(
                         "\n⚠️  No semantic index. Run `seekr index --semantic` for best results."
                     );
                     println!("   Falling back to lexical search only.\n");
+                    let bm25_results = indexer.search(&query, limit, &filter)?;
                     let printer = ResultPrinter::new(context);
                     printer.print_results(&bm25_results)?;
                     return Ok(());
                 }
 
-                let sem_results = semantic_indexer.search(&query, limit * 2)?;
+                let sem_results = semantic_indexer.search(&query, limit * 2, &filter)?;
+
+                // Chunk-level BM25 documents fuse 1:1 with semantic chunks;
+                // otherwise both sides are fused per file
+                let chunk_level = indexer.has_chunks();
+                let chunk_id = |path: &str, start: usize, end: usize| {
+                    chunk_level.then(|| format!("{}:{}-{}", path, start, end))
+                };
 
                 // Convert to RankedResults
-                let lexical: Vec<ranker::RankedResult> = bm25_results
-                    .iter()
-                    .map(|r| ranker::RankedResult {
-                        file_path: r.file_path.clone(),
-                        score: r.score,
-                        source: ranker::SearchSource::Lexical,
-                        start_line: r.matching_lines.first().map(|(l, _)| *l).unwrap_or(1),
-                        end_line: r.matching_lines.last().map(|(l, _)| *l).unwrap_or(1),
-                        content_preview: r
-                            .matching_lines
-                            .first()
-                            .map(|(_, c)| c.clone())
-                            .unwrap_or_default(),
-                        name: None,
-                    })
-                    .collect();
+                let lexical: Vec<ranker::RankedResult> = if chunk_level {
+                    indexer
+                        .search_chunks(&query, limit * 2, &filter)?
+                        .into_iter()
+                        .map(|r| ranker::RankedResult {
+                            chunk_id: chunk_id(&r.file_path, r.start_line, r.end_line),
+                            file_path: r.file_path,
+                            score: r.score,
+                            source: ranker::SearchSource::Lexical,
+                            start_line: r.start_line,
+                            end_line: r.end_line,
+                            content_preview: r
+                                .matching_lines
+                                .first()
+                                .map(|(_, c)| c.trim().to_string())
+                                .unwrap_or_default(),
+                            name: r.name,
+                        })
+                        .collect()
+                } else {
+                    indexer
+                        .search(&query, limit * 2, &filter)?
+                        .iter()
+                        .map(|r| ranker::RankedResult {
+                            file_path: r.file_path.clone(),
+                            chunk_id: None,
+                            score: r.score,
+                            source: ranker::SearchSource::Lexical,
+                            start_line: r.matching_lines.first().map(|(l, _)| *l).unwrap_or(1),
+                            end_line: r.matching_lines.last().map(|(l, _)| *l).unwrap_or(1),
+                            content_preview: r
+                                .matching_lines
+                                .first()
+                                .map(|(_, c)| c.clone())
+                                .unwrap_or_default(),
+                            name: None,
+                        })
+                        .collect()
+                };
 
                 let semantic_ranked: Vec<ranker::RankedResult> = sem_results
                     .iter()
                     .map(|r| ranker::RankedResult {
                         file_path: r.file_path.clone(),
+                        chunk_id: chunk_id(&r.file_path, r.start_line, r.end_line),
                         score: r.similarity_score,
                         source: ranker::SearchSource::Semantic,
                         start_line: r.start_line,
@@ -238,14 +369,15 @@ fn main() -> Result<()> {
                         println!(
                             "{} {} {} {}",
                             format!("[{}]", i + 1).cyan().bold(),
-                            result.file_path.blue().bold(),
+                            output::styled_path(&result.file_path),
                             "·".dimmed(),
                             format!("score: {:.3}", result.score).dimmed()
                         );
                         println!(
-                            "    {} {} {}",
+                            "    {} {}-{} {}",
                             "lines:".dimmed(),
-                            format!("{}-{}", result.start_line, result.end_line),
+                            result.start_line,
+                            result.end_line,
                             format!("[{:?}]", result.source).dimmed()
                         );
                         if let Some(name) = &result.name {
@@ -267,16 +399,15 @@ fn main() -> Result<()> {
                 }
             } else if semantic {
                 // Semantic search
-                let home = dirs::home_dir().expect("Could not find home directory");
-                let semantic_path = home.join(".seekr");
-                let mut semantic_indexer = semantic::SemanticIndexer::new(&semantic_path)?;
+                let mut semantic_indexer =
+                    semantic::SemanticIndexer::open(&workspace.semantic_path())?;
 
                 if !semantic_indexer.index_exists() {
                     println!("\n❌ No semantic index found. Run `seekr index --semantic` first.");
                     return Ok(());
                 }
 
-                let results = semantic_indexer.search(&query, limit)?;
+                let results = semantic_indexer.search(&query, limit, &filter)?;
 
                 if results.is_empty() {
                     println!("\n{}", "No results found.".yellow());
@@ -287,16 +418,17 @@ fn main() -> Result<()> {
                         println!(
                             "{} {} {} {}",
                             format!("[{}]", i + 1).cyan().bold(),
-                            result.file_path.blue().bold(),
+                            output::styled_path(&result.file_path),
                             "·".dimmed(),
                             format!("similarity: {:.2}", result.similarity_score).dimmed()
                         );
                         println!(
-                            "    {} {} {} {}",
+                            "    {} {} {} {}-{}",
                             "type:".dimmed(),
                             result.chunk_type.magenta(),
                             "lines:".dimmed(),
-                            format!("{}-{}", result.start_line, result.end_line)
+                            result.start_line,
+                            result.end_line
                         );
                         if let Some(name) = &result.name {
                             println!("    {} {}", "name:".dimmed(), name);
@@ -306,23 +438,61 @@ fn main() -> Result<()> {
                     }
                 }
             } else {
-                // BM25 lexical search
-                let index_path = Indexer::default_index_path()?;
-                let indexer = Indexer::open(&index_path)?;
-                let results = indexer.search(&query, limit)?;
+                // BM25 lexical or regex search, over the workspace and/or its dependencies
+                let mut index_paths = Vec::new();
+                if scope != "deps" {
+                    index_paths.push(index_path);
+                }
+                if scope != "workspace" {
+                    index_paths.push(deps_path);
+                }
+                let mut results = Vec::new();
+                for index_path in &index_paths {
+                    let indexer = Indexer::open(index_path)?;
+                    results.extend(if regex {
+                        indexer.search_regex(&query, limit, &filter)?
+                    } else {
+                        indexer.search(&query, limit, &filter)?
+                    });
+                }
+                results.sort_by(|a, b| b.score.total_cmp(&a.score));
+                results.truncate(limit);
 
                 if json {
                     let json_results: Vec<serde_json::Value> = results
                         .iter()
                         .map(|r| {
-                            serde_json::json!({
+                            let matching_lines: Vec<serde_json::Value> = r
+                                .matching_lines
+                                .iter()
+                                .enumerate()
+                                .map(|(i, (l, c))| {
+                                    let mut line = serde_json::json!({"line": l, "content": c});
+                                    // Byte offsets of each match within the line (--regex)
+                                    if let Some(spans) = r.match_spans.get(i) {
+                                        line["matches"] = spans
+                                            .iter()
+                                            .map(|(start, end)| {
+                                                serde_json::json!({"start": start, "end": end})
+                                            })
+                                            .collect();
+                                    }
+                                    line
+                                })
+                                .collect();
+                            let mut value = serde_json::json!({
                                 "file": r.file_path,
                                 "score": r.score,
                                 "language": r.language,
-                                "matching_lines": r.matching_lines.iter().map(|(l, c)| {
-                                    serde_json::json!({"line": l, "content": c})
-                                }).collect::<Vec<_>>()
-                            })
+                                "matching_lines": matching_lines
+                            });
+                            if let Some(revision) = &r.revision {
+                                value["revision"] = serde_json::json!(revision);
+                            }
+                            if let Some(encoding) = &r.encoding {
+                                value["encoding"] = serde_json::json!(encoding);
+                            }
+                            value
                         })
                         .collect();
                     println!("{}", serde_json::to_string_pretty(&json_results)?);
@@ -332,11 +502,143 @@ fn main() -> Result<()> {
                 }
             }
         }
+        Commands::Def {
+            name,
+            prefix,
+            fuzzy,
+            kind,
+            limit,
+            lang,
+            path,
+            exclude,
+            json,
+        } => {
+            let workspace = Workspace::discover(&std::env::current_dir()?)?;
+            let filter = filter::SearchFilter::new(&lang, &path, &exclude)?;
+            let indexer = Indexer::open(&workspace.index_path())?;
+
+            let mode = if prefix {
+                SymbolMatch::Prefix
+            } else if fuzzy {
+                SymbolMatch::Fuzzy
+            } else {
+                SymbolMatch::Exact
+            };
+            let mut results = indexer.find_symbols(&name, mode, kind.as_deref(), limit, &filter)?;
+
+            // Nothing named exactly that: suggest close spellings instead (not in
+            // JSON, where suggestions would pass for definitions)
+            let mut suggestions = false;
+            if results.is_empty() && mode == SymbolMatch::Exact && !json {
+                results = indexer.find_symbols(
+                    &name,
+                    SymbolMatch::Fuzzy,
+                    kind.as_deref(),
+                    limit,
+                    &filter,
+                )?;
+                suggestions = !results.is_empty();
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else if results.is_empty() {
+                println!(
+                    "\n{}",
+                    format!("No definitions found for '{name}'").yellow()
+                );
+            } else {
+                if suggestions {
+                    println!(
+                        "\n{}",
+                        format!("No exact definition of '{name}'. Did you mean:").yellow()
+                    );
+                }
+                println!();
+                for symbol in &results {
+                    let qualified = match &symbol.container {
+                        Some(container) => format!("{}::{}", container, symbol.name),
+                        None => symbol.name.clone(),
+                    };
+                    println!(
+                        "{} {} {}",
+                        format!("{}:{}", symbol.file_path, symbol.start_line)
+                            .blue()
+                            .bold(),
+                        symbol.kind.magenta(),
+                        qualified.bold()
+                    );
+                }
+            }
+        }
+        Commands::LogSearch {
+            query,
+            author,
+            since,
+            path,
+            limit,
+            json,
+        } => {
+            let workspace = Workspace::discover(&std::env::current_dir()?)?;
+            let index_path = workspace.history_index_path();
+            if !index_path.exists() {
+                println!("\n❌ No history index found. Run `seekr index --history` first.");
+                return Ok(());
+            }
+
+            let filter = history::HistoryFilter::new(author, since.as_deref(), &path)?;
+            let results =
+                history::HistoryIndexer::open(&index_path)?.search(&query, limit, &filter)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else if results.is_empty() {
+                println!("\n{}", "No results found.".yellow());
+            } else {
+                println!("\n{} {} results:", "Found".green(), results.len());
+
+                let mut previous_commit = None;
+                for result in &results {
+                    // Consecutive hunks of one commit share its header
+                    if previous_commit != Some(&result.commit) {
+                        println!(
+                            "\n{} {} {}",
+                            result.commit[..result.commit.len().min(10)].yellow().bold(),
+                            result.date.dimmed(),
+                            result.author.dimmed()
+                        );
+                        println!("    {}", result.summary().bold());
+                        previous_commit = Some(&result.commit);
+                    }
+
+                    let (Some(file_path), Some(hunk)) = (&result.file_path, &result.hunk) else {
+                        continue;
+                    };
+                    println!(
+                        "    {} {}",
+                        format!("{}:{}", file_path, result.line.unwrap_or(1))
+                            .blue()
+                            .bold(),
+                        hunk.dimmed()
+                    );
+                    for change in result.matching_changes(&query, 5) {
+                        let change: String = change.chars().take(120).collect();
+                        if change.starts_with('+') {
+                            println!("      {}", change.green());
+                        } else {
+                            println!("      {}", change.red());
+                        }
+                    }
+                }
+                println!();
+            }
+        }
         Commands::Watch => {
             tracing::info!("Starting file watcher...");
             let path = std::env::current_dir()?;
+            let workspace = Workspace::discover(&path).or_else(|_| Workspace::for_root(&path))?;
             let file_watcher = watcher::FileWatcher::default();
-            file_watcher.watch(&path)?;
+            file_watcher.watch(&workspace)?;
         }
         Commands::Similar { file, range } => {
             tracing::info!("Finding similar code to {:?} range {:?}", file, range);
@@ -344,23 +646,35 @@ fn main() -> Result<()> {
             println!("⚠️  Similar command not yet implemented");
         }
         Commands::Config { key, value } => {
+            let mut config = config::Config::load()?;
             if let Some(val) = value {
                 tracing::info!("Setting config: {} = {}", key, val);
-                // TODO: Implement config management
-                println!("⚠️  Config command not yet implemented");
+                config.set(&key, &val)?;
+                config.save()?;
+                println!("✅ {} = {}", key, config.get(&key)?);
+                println!(
+                    "   {}",
+                    "Run `seekr index --force` to apply it to existing indexes.".dimmed()
+                );
             } else {
                 tracing::info!("Getting config: {}", key);
-                println!("⚠️  Config command not yet implemented");
+                println!("{}", config.get(&key)?);
             }
         }
         Commands::Init { path } => {
             let path = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let workspace = Workspace::for_root(&path)?;
+            let path = workspace.root().to_path_buf();
+            let _lock = WorkspaceLock::acquire(&workspace, "seekr init")?;
             println!("\n🚀 Initializing seekr in {:?}...\n", path);
 
             // Step 1: Build BM25 index
             println!("📚 Step 1/2: Building lexical index...");
-            let mut indexer = Indexer::new(&path, true)?;
-            let stats = indexer.index_directory(&path)?;
+            let roots = workspace.roots()?;
+            let mut indexer = Indexer::new(&workspace, true)?;
+            let stats = indexer.index_directory(&roots)?;
+            indexer.publish()?;
+            workspace.register()?;
             println!(
                 "   ✅ Indexed {} files ({} lines) in {:.2}s\n",
                 stats.files_indexed, stats.total_lines, stats.duration_secs
@@ -370,36 +684,12 @@ fn main() -> Result<()> {
             println!("🧠 Step 2/2: Building semantic index...");
             println!("   (This downloads a 23MB model on first run)\n");
 
-            let home = dirs::home_dir().expect("Could not find home directory");
-            let semantic_path = home.join(".seekr");
-            let mut semantic_indexer = semantic::SemanticIndexer::new(&semantic_path)?;
-
-            // Collect files for semantic indexing
-            let mut files: Vec<(std::path::PathBuf, String)> = Vec::new();
-            let walker = ignore::WalkBuilder::new(&path)
-                .hidden(true)
-                .git_ignore(true)
-                .build();
-
-            for entry in walker.filter_map(|e| e.ok()) {
-                let entry_path = entry.path();
-                if entry_path.is_file() {
-                    if let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) {
-                        if matches!(ext, "rs" | "py" | "js" | "jsx" | "ts" | "tsx" | "go") {
-                            if let Ok(content) = std::fs::read_to_string(entry_path) {
-                                files.push((entry_path.to_path_buf(), content));
-                            }
-                        }
-                    }
-                }
-            }
+            let staged = workspace.stage_semantic()?;
+            let mut semantic_indexer = semantic::SemanticIndexer::new(&staged.path())?;
 
-            let file_refs: Vec<(&std::path::Path, String)> = files
-                .iter()
-                .map(|(p, c)| (p.as_path(), c.clone()))
-                .collect();
-
-            let sem_stats = semantic_indexer.index_files(&file_refs)?;
+            let files = collect_semantic_files(&roots, 0)?;
+            let sem_stats = semantic_indexer.index_files(&files)?;
+            staged.publish()?;
             println!(
                 "   ✅ Created {} chunks, {} embeddings in {:.2}s\n",
                 sem_stats.chunks_created, sem_stats.embeddings_generated, sem_stats.duration_secs
@@ -413,22 +703,634 @@ fn main() -> Result<()> {
             println!("   seekr search \"query\" --hybrid       # Best of both");
             println!("   seekr watch                         # Auto-reindex on changes");
         }
-        Commands::Status => {
-            let index_path = Indexer::default_index_path()?;
-            match Indexer::get_status(&index_path) {
-                Ok(status) => {
-                    println!("\n📊 Index Status");
-                    println!("   Path: {:?}", index_path);
-                    println!("   Documents: {}", status.num_docs);
-                    println!("   Size: {:.2} MB", status.size_bytes as f64 / 1_048_576.0);
-                    println!("   Healthy: {}", if status.healthy { "✅" } else { "❌" });
+        Commands::LsFiles {
+            path,
+            ignored,
+            json,
+            threads,
+        } => {
+            let path = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let workspace = Workspace::for_root(&path)?;
+            list_files(&workspace, threads, ignored, json)?;
+        }
+        Commands::Status { json } => {
+            let report = Workspace::discover(&std::env::current_dir()?)
+                .and_then(|workspace| status::collect(&workspace));
+            let report = match report {
+                Ok(report) => report,
+                Err(_) if json => anyhow::bail!("No index found. Run `seekr index` first."),
+                Err(_) => {
+                    println!("\n❌ No index found. Run `seekr index` first.");
+                    return Ok(());
+                }
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+
+            println!("\n📊 Index Status");
+            println!("   Workspace: {}", report.workspace.display());
+            for root in report.roots.iter().filter(|root| !root.main) {
+                println!("   Root: @{} ({})", root.label, root.path.display());
+            }
+            println!("   Path: {:?}", report.index_path);
+            println!(
+                "   Last indexed: {}",
+                report
+                    .last_indexed
+                    .map(format_age)
+                    .unwrap_or_else(|| "unknown".to_string())
+            );
+            println!("   Documents: {}", report.documents);
+            println!("   Size: {:.2} MB", report.size_bytes as f64 / 1_048_576.0);
+            println!("   Healthy: {}", if report.healthy { "✅" } else { "❌" });
+            if !report.healthy {
+                println!("   Run `seekr doctor` for details.");
+            }
+
+            if !report.languages.is_empty() {
+                let mut languages: Vec<_> = report.languages.iter().collect();
+                languages.sort_by(|a, b| b.1.lines.cmp(&a.1.lines).then(a.0.cmp(b.0)));
+
+                println!("\n📚 Languages");
+                for (language, stats) in languages {
+                    println!(
+                        "   {:<12} {:>6} files {:>9} lines",
+                        language, stats.files, stats.lines
+                    );
                 }
+            }
+
+            println!("\n🔄 Changes on disk");
+            let changes = report.changes;
+            if changes.is_empty() {
+                println!("   ✅ Up to date");
+            } else {
+                println!(
+                    "   {} stale, {} new, {} deleted",
+                    changes.stale, changes.new, changes.deleted
+                );
+                println!("   Run `seekr index` to update.");
+            }
+
+            println!("\n🧠 Semantic Index");
+            match &report.semantic {
+                None => println!("   Not built. Run `seekr index --semantic` to enable it."),
+                Some(semantic) => {
+                    println!(
+                        "   Model: {} ({} dimensions)",
+                        semantic.model, semantic.dimension
+                    );
+                    println!(
+                        "   Vectors: {} ({:.2} MB)",
+                        semantic.vectors,
+                        semantic.size_bytes as f64 / 1_048_576.0
+                    );
+                    if !semantic.chunks_by_type.is_empty() {
+                        println!("   By type: {}", format_counts(&semantic.chunks_by_type));
+                        println!(
+                            "   By language: {}",
+                            format_counts(&semantic.chunks_by_language)
+                        );
+                    }
+                    if let Some(problem) = &semantic.problem {
+                        println!("   ❌ {}", problem);
+                        println!("   Run `seekr index --semantic` to rebuild it.");
+                    }
+                }
+            }
+        }
+        Commands::Doctor { fix } => {
+            let workspace = match Workspace::discover(&std::env::current_dir()?) {
+                Ok(ws) => ws,
                 Err(_) => {
                     println!("\n❌ No index found. Run `seekr index` first.");
+                    return Ok(());
                 }
+            };
+
+            println!("\n🩺 Checking {}\n", workspace.root().display());
+            let checks = doctor::diagnose(&workspace)?;
+            print_checks(&checks);
+
+            let mut repairs: Vec<doctor::Repair> =
+                checks.into_iter().filter_map(|check| check.repair).collect();
+            if repairs.is_empty() {
+                println!("\n{}", "Everything looks healthy.".green());
+                return Ok(());
+            }
+            if !fix {
+                println!(
+                    "\n{} Run `seekr doctor --fix` to repair.",
+                    format!("{} problem(s) found.", repairs.len()).yellow()
+                );
+                return Ok(());
+            }
+
+            let _lock = WorkspaceLock::acquire(&workspace, "seekr doctor --fix")?;
+
+            // A rebuild also brings the index up to date
+            if repairs.contains(&doctor::Repair::RebuildIndex) {
+                repairs.retain(|repair| *repair != doctor::Repair::UpdateIndex);
+            }
+            println!();
+            for repair in &repairs {
+                println!("🔧 Trying to {}...", repair);
+                apply_repair(&workspace, repair)?;
             }
+
+            println!("\n🩺 Checking again\n");
+            let checks = doctor::diagnose(&workspace)?;
+            print_checks(&checks);
+            let remaining = checks
+                .iter()
+                .filter(|check| check.status == doctor::CheckStatus::Problem)
+                .count();
+            if remaining == 0 {
+                println!("\n{}", "All problems repaired.".green());
+            } else {
+                println!("\n{}", format!("{} problem(s) remain.", remaining).red());
+            }
+        }
+        Commands::Workspaces => {
+            let registry = WorkspaceRegistry::load()?;
+            if registry.entries().is_empty() {
+                println!("\n{}", "No workspaces indexed yet.".yellow());
+                return Ok(());
+            }
+
+            let cwd = std::env::current_dir()?.canonicalize()?;
+            let current = registry.find_for(&cwd).map(|w| w.id.clone());
+
+            println!("\n📂 Indexed workspaces\n");
+            for entry in registry.entries() {
+                let marker = if current.as_deref() == Some(entry.id.as_str()) {
+                    "*".green().bold()
+                } else {
+                    " ".normal()
+                };
+                println!(
+                    "{} {}",
+                    marker,
+                    entry.root.display().to_string().blue().bold()
+                );
+                println!(
+                    "    {} {} {} {}",
+                    "id:".dimmed(),
+                    entry.id,
+                    "last indexed:".dimmed(),
+                    format_age(entry.last_indexed)
+                );
+                for root in &entry.added_roots {
+                    println!("    {} {}", "root:".dimmed(), root.display());
+                }
+            }
+        }
+        Commands::Root { action } => {
+            let cwd = std::env::current_dir()?;
+            let workspace = Workspace::discover(&cwd).or_else(|_| Workspace::for_root(&cwd))?;
+
+            match action {
+                RootAction::Add { path, label } => {
+                    let _lock = WorkspaceLock::acquire(&workspace, "seekr root add")?;
+                    let before = workspace.roots()?;
+                    let root = workspace.add_root(&path, label.as_deref())?;
+                    println!(
+                        "\n➕ Added {} to {} as {}",
+                        root.path.display(),
+                        workspace.root().display(),
+                        format!("@{}", root.label).cyan()
+                    );
+                    update_roots(&workspace, &before)?;
+                    if semantic::SemanticIndexer::summary(&workspace.semantic_path()).is_some() {
+                        println!(
+                            "   {}",
+                            "Run `seekr index --semantic` to embed its files.".dimmed()
+                        );
+                    }
+                }
+                RootAction::Remove { label } => {
+                    let _lock = WorkspaceLock::acquire(&workspace, "seekr root remove")?;
+                    let before = workspace.roots()?;
+                    let root = workspace.remove_root(&label)?;
+                    println!(
+                        "\n➖ Removed {} ({}) from {}",
+                        format!("@{}", root.label).cyan(),
+                        root.path.display(),
+                        workspace.root().display()
+                    );
+                    update_roots(&workspace, &before)?;
+                }
+                RootAction::List => {
+                    println!("\n🌳 Roots of {}\n", workspace.root().display());
+                    for root in workspace.roots()? {
+                        if root.main {
+                            println!("  {:<16} {}", "(main)".dimmed(), root.path.display());
+                        } else {
+                            println!(
+                                "  {:<16} {}",
+                                format!("@{}", root.label).cyan(),
+                                root.path.display()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Bring the index in line with the workspace roots after adding or removing one
+///
+/// Only files that enter, leave or move between roots are indexed or dropped.
+fn update_roots(workspace: &Workspace, before: &[workspace::Root]) -> Result<()> {
+    let after = workspace.roots()?;
+    let chunks = Indexer::has_chunks_at(&workspace.index_path());
+
+    let mut indexer = Indexer::new(workspace, false)?.with_chunks(chunks);
+    let mut file_cache = cache::FileCache::load(indexer.path())?;
+    let mut removed = indexer.apply_root_change(before, &after, &mut file_cache)?;
+    let stats = indexer.index_directory_incremental(&after, &mut file_cache)?;
+    indexer.publish()?;
+    workspace.register()?;
+
+    // Files that left the workspace (or changed path) must also leave the semantic index
+    removed.extend(stats.deleted_files);
+    if !removed.is_empty() {
+        semantic::SemanticIndexer::new(&workspace.semantic_path())?.remove_files(&removed)?;
+    }
+    println!(
+        "   Indexed {} files, removed {} in {:.2}s",
+        stats.files_indexed,
+        removed.len(),
+        stats.duration_secs
+    );
+    Ok(())
+}
+
+/// Print every candidate file of a workspace with its language and indexing decision
+fn list_files(workspace: &Workspace, threads: usize, ignored: bool, json: bool) -> Result<()> {
+    let files = indexer::list_files(&workspace.roots()?, threads, ignored)?;
+
+    if json {
+        let json_files: Vec<serde_json::Value> = files
+            .iter()
+            .map(|file| {
+                let mut value = serde_json::json!({
+                    "path": file.path,
+                    "language": file.language,
+                    "decision": file.decision.label(),
+                });
+                if let indexer::FileDecision::Skipped(reason) = file.decision {
+                    value["reason"] = serde_json::json!(reason.to_string());
+                }
+                value
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json_files)?);
+        return Ok(());
+    }
+
+    let mut counts = std::collections::BTreeMap::new();
+    let mut skipped = std::collections::BTreeMap::new();
+    for file in &files {
+        let decision = match file.decision {
+            indexer::FileDecision::Indexed => file.decision.label().green(),
+            indexer::FileDecision::Skipped(reason) => {
+                *skipped.entry(reason).or_insert(0) += 1;
+                file.decision.label().yellow()
+            }
+            _ => file.decision.label().dimmed(),
+        };
+        *counts.entry(file.decision.label().to_string()).or_insert(0) += 1;
+
+        let language = file.language.as_deref().unwrap_or("-");
+        match file.decision {
+            indexer::FileDecision::Skipped(reason) => println!(
+                "{:<11} {:<12} {} {}",
+                decision,
+                language.magenta(),
+                output::styled_path(&file.path),
+                format!("({})", reason).dimmed()
+            ),
+            _ => println!(
+                "{:<11} {:<12} {}",
+                decision,
+                language.magenta(),
+                output::styled_path(&file.path)
+            ),
+        }
+    }
+
+    println!("\n📋 {} files: {}", files.len(), format_counts(&counts));
+    if !skipped.is_empty() {
+        println!("   Skipped: {}", format_skipped(&skipped));
+    }
+    Ok(())
+}
+
+/// Index the sources of the workspace's dependencies into the deps index
+fn index_dependencies(
+    workspace: &Workspace,
+    roots: &[workspace::Root],
+    force: bool,
+    threads: usize,
+) -> Result<()> {
+    println!("\n📦 Indexing dependency sources...");
+
+    let dependencies = deps::discover(roots)?;
+    let mut indexer = Indexer::for_dependencies(workspace, force)?.with_threads(threads);
+    let mut file_cache = cache::FileCache::load(indexer.path())?;
+
+    // Dependencies that were upgraded or dropped leave the index
+    let before = deps::load_indexed(indexer.path())?;
+    let dropped = indexer.apply_root_change(&before, &dependencies, &mut file_cache)?;
+    let stats = indexer.index_directory_incremental(&dependencies, &mut file_cache)?;
+    deps::save_indexed(indexer.path(), &dependencies)?;
+    indexer.publish()?;
+
+    if dependencies.is_empty() {
+        println!("   No dependency sources found (Cargo.lock, package.json or a virtualenv).");
+        return Ok(());
+    }
+    let mut ecosystems = std::collections::BTreeMap::new();
+    for dependency in &dependencies {
+        *ecosystems
+            .entry(deps::ecosystem(dependency).to_string())
+            .or_insert(0) += 1;
+    }
+    println!("   Dependencies: {}", format_counts(&ecosystems));
+    println!("   Files indexed: {}", stats.files_indexed);
+    if stats.files_deleted + dropped.len() > 0 {
+        println!("   Files removed: {}", stats.files_deleted + dropped.len());
+    }
+    println!("   Total lines: {}", stats.total_lines);
+    println!("   Time: {:.2}s", stats.duration_secs);
+    println!("\n   Search them with: seekr search <QUERY> --scope deps");
+
+    Ok(())
+}
+
+/// Index a git revision of a workspace into its own snapshot index
+fn index_revision(
+    workspace: &Workspace,
+    rev: &str,
+    force: bool,
+    chunks: bool,
+    threads: usize,
+) -> Result<()> {
+    let revision = git::resolve(workspace.root(), rev)?;
+    let index_path = workspace.revision_index_path(&revision.sha);
+
+    // Commits are immutable: an existing snapshot only needs rebuilding after a format change
+    let indexed = indexer::stale_version(&index_path, indexer::SCHEMA_VERSION).is_none()
+        && Indexer::get_status(&index_path).is_ok_and(|status| status.num_docs > 0);
+    if indexed && !force {
+        println!(
+            "\n✨ {} ({}) is already indexed. Use --force to rebuild it.",
+            rev,
+            revision.short_sha()
+        );
+        return Ok(());
+    }
+
+    let mut indexer = Indexer::for_revision(workspace, &revision, true)?
+        .with_chunks(chunks)
+        .with_threads(threads);
+    let stats = indexer.index_revision(workspace.root(), &revision)?;
+
+    println!(
+        "\n✨ Indexed {} ({}) from git",
+        rev.bold(),
+        revision.short_sha()
+    );
+    println!("   Files indexed: {}", stats.files_indexed);
+    if !stats.skipped.is_empty() {
+        println!("   Files skipped: {}", format_skipped(&stats.skipped));
+    }
+    println!("   Total lines: {}", stats.total_lines);
+    println!("   Time: {:.2}s", stats.duration_secs);
+    println!("\n   Search it with: seekr search <QUERY> --rev {}", rev);
+
+    Ok(())
+}
+
+/// Print doctor checks as an aligned list
+fn print_checks(checks: &[doctor::Check]) {
+    for check in checks {
+        let icon = match check.status {
+            doctor::CheckStatus::Ok => "✅",
+            doctor::CheckStatus::Info => "➖",
+            doctor::CheckStatus::Problem => "❌",
+        };
+        println!("  {} {:<16} {}", icon, check.name, check.detail);
+        if let Some(repair) = &check.repair {
+            println!("     {:<16} {}", "", format!("fix: {}", repair).dimmed());
         }
     }
+}
 
+/// Carry out a `seekr doctor --fix` repair
+fn apply_repair(workspace: &Workspace, repair: &doctor::Repair) -> Result<()> {
+    match repair {
+        doctor::Repair::RebuildIndex | doctor::Repair::UpdateIndex => {
+            let force = *repair == doctor::Repair::RebuildIndex;
+            let chunks = Indexer::has_chunks_at(&workspace.index_path());
+
+            let mut indexer = Indexer::new(workspace, force)?.with_chunks(chunks);
+            let mut file_cache = cache::FileCache::load(indexer.path())?;
+            let stats = indexer.index_directory_incremental(&workspace.roots()?, &mut file_cache)?;
+            indexer.publish()?;
+            workspace.register()?;
+
+            // Deleted files must also leave the semantic index
+            if !stats.deleted_files.is_empty() {
+                semantic::SemanticIndexer::new(&workspace.semantic_path())?
+                    .remove_files(&stats.deleted_files)?;
+            }
+            println!(
+                "   Indexed {} files, removed {}",
+                stats.files_indexed, stats.files_deleted
+            );
+        }
+        doctor::Repair::RebuildSemantic => {
+            let staged = workspace.stage_semantic()?;
+            let files = collect_semantic_files(&workspace.roots()?, 0)?;
+            let stats = semantic::SemanticIndexer::new(&staged.path())?.index_files(&files)?;
+            staged.publish()?;
+            println!("   Embedded {} chunks", stats.embeddings_generated);
+        }
+        doctor::Repair::RebuildHistory => {
+            let mut history_indexer = history::HistoryIndexer::create(workspace, true)?;
+            let stats = history_indexer.index_repository(workspace.root())?;
+            history_indexer.publish()?;
+            println!("   Indexed {} commits", stats.commits_indexed);
+        }
+        doctor::Repair::DownloadModel => {
+            embedder::Embedder::new()?;
+        }
+        doctor::Repair::RewriteWorkspaceFile => {
+            Indexer::write_workspace_file(&workspace.index_path(), workspace)?;
+        }
+        doctor::Repair::ForgetWorkspaces(ids) => {
+            WorkspaceRegistry::update(|registry| {
+                for id in ids {
+                    registry.remove(id);
+                }
+            })?;
+            for id in ids {
+                let data_dir = Workspace::data_dir_for_id(id)?;
+                if data_dir.exists() {
+                    std::fs::remove_dir_all(data_dir)?;
+                }
+            }
+            println!("   Forgot {} workspace(s)", ids.len());
+        }
+    }
     Ok(())
 }
+
+/// Collect source files for semantic indexing, keyed by workspace path
+///
+/// Files are read in parallel; the result is sorted by path so chunk order is stable.
+fn collect_semantic_files(
+    roots: &[workspace::Root],
+    threads: usize,
+) -> Result<Vec<semantic::SourceFile>> {
+    let files = std::sync::Mutex::new(Vec::new());
+
+    for root in roots {
+        let classifier = classify::FileClassifier::for_root(&root.path)?;
+        root.walker(roots)
+            .threads(threads)
+            .for_each_file(|entry_path| {
+                if archive::is_archive(entry_path) {
+                    return collect_archive_members(root, &classifier, entry_path, &files);
+                }
+                if notebook::is_notebook(entry_path) {
+                    collect_notebook_cells(root, &classifier, entry_path, &files);
+                    return Ok(());
+                }
+
+                // Only languages with a tree-sitter grammar get embeddings
+                let language = chunker::Language::from_path(entry_path);
+                if language.tree_sitter_language().is_none() {
+                    return Ok(());
+                }
+                if let Ok(text) = classifier.read(entry_path, &root.relative_path(entry_path)) {
+                    files.lock().unwrap().push(semantic::SourceFile {
+                        path: root.workspace_path(entry_path).into(),
+                        language,
+                        content: text.content,
+                    });
+                }
+                Ok(())
+            })?;
+    }
+
+    let mut files = files.into_inner().unwrap();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Add the source members of an archive to the files collected for semantic indexing
+fn collect_archive_members(
+    root: &workspace::Root,
+    classifier: &classify::FileClassifier,
+    path: &std::path::Path,
+    files: &std::sync::Mutex<Vec<semantic::SourceFile>>,
+) -> Result<()> {
+    let archive_path = root.workspace_path(path);
+    let relative_path = root.relative_path(path);
+
+    let limit = classifier.max_file_size();
+    let result = archive::for_each_member(path, limit, |member, language, bytes| {
+        let language = chunker::Language::from_name(language);
+        if language.tree_sitter_language().is_none() {
+            return Ok(());
+        }
+        let member_relative = archive::member_path(&relative_path, member);
+        if let Ok(text) = classifier.check_bytes(&member_relative, bytes) {
+            files.lock().unwrap().push(semantic::SourceFile {
+                path: archive::member_path(&archive_path, member).into(),
+                language,
+                content: text.content,
+            });
+        }
+        Ok(())
+    });
+    // The lexical indexer already reported unreadable archives
+    result.or_else(|_| Ok(()))
+}
+
+/// Add the code cells of a notebook to the files collected for semantic indexing
+fn collect_notebook_cells(
+    root: &workspace::Root,
+    classifier: &classify::FileClassifier,
+    path: &std::path::Path,
+    files: &std::sync::Mutex<Vec<semantic::SourceFile>>,
+) {
+    // The lexical indexer already reported unreadable notebooks
+    let Ok(notebook) = notebook::Notebook::read(path) else {
+        return;
+    };
+    let notebook_path = root.workspace_path(path);
+    let relative_path = root.relative_path(path);
+
+    for (cell, language) in notebook.indexed_cells(false) {
+        let language = chunker::Language::from_name(language);
+        if language.tree_sitter_language().is_none() {
+            continue;
+        }
+        let cell_relative = notebook::cell_path(&relative_path, cell.number);
+        let source = cell.source.clone().into_bytes();
+        if let Ok(text) = classifier.check_bytes(&cell_relative, source) {
+            files.lock().unwrap().push(semantic::SourceFile {
+                path: notebook::cell_path(&notebook_path, cell.number).into(),
+                language,
+                content: text.content,
+            });
+        }
+    }
+}
+
+/// Format skip counts as "12 (9 generated, 3 binary)"
+fn format_skipped(skipped: &std::collections::BTreeMap<classify::SkipReason, usize>) -> String {
+    let total: usize = skipped.values().sum();
+    let reasons: Vec<String> = skipped
+        .iter()
+        .map(|(reason, count)| format!("{} {}", count, reason))
+        .collect();
+    format!("{} ({})", total, reasons.join(", "))
+}
+
+/// Format counts as "function 12, method 9", largest first
+fn format_counts(counts: &std::collections::BTreeMap<String, usize>) -> String {
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    counts
+        .iter()
+        .map(|(name, count)| format!("{} {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format a seconds-since-epoch timestamp as a coarse age ("5 min ago")
+fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let age = now.saturating_sub(timestamp);
+
+    match age {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", age / 60),
+        3600..=86_399 => format!("{} h ago", age / 3600),
+        _ => format!("{} days ago", age / 86_400),
+    }
+}
@@ -0,0 +1,209 @@
+//! Jupyter notebooks
+//!
+//! A notebook is a JSON document, so it can't be indexed as a source file.
+//! Instead each code cell is indexed as a document of its own, in the
+//! kernel's language, under `<notebook>#cell-<n>` where `n` counts every cell
+//! from 1 as Jupyter shows them. Line numbers of a match are lines within the
+//! cell. Markdown cells are only indexed with `notebook_markdown` set, and
+//! cell outputs never are.
+//!
+//! As with archives, every cell document carries the notebook's path as its
+//! source path, so a changed notebook replaces all of its cells at once.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Separates the notebook path from the cell number in a virtual path
+pub const CELL_SEPARATOR: &str = "#cell-";
+
+/// Language of notebooks that don't name their kernel's language
+const DEFAULT_LANGUAGE: &str = "python";
+
+/// Whether a file is a Jupyter notebook
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Virtual path of a notebook cell
+pub fn cell_path(notebook_path: &str, number: usize) -> String {
+    format!("{}{}{}", notebook_path, CELL_SEPARATOR, number)
+}
+
+/// Split a virtual path into the notebook path and the cell number
+pub fn split_cell_path(path: &str) -> Option<(&str, usize)> {
+    let (notebook_path, number) = path.rsplit_once(CELL_SEPARATOR)?;
+    Some((notebook_path, number.parse().ok()?))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Code,
+    Markdown,
+    /// Raw cells and anything newer; never indexed
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cell {
+    /// Position in the notebook, from 1
+    pub number: usize,
+    pub kind: CellKind,
+    pub source: String,
+}
+
+/// The cells of a notebook and the language of its kernel
+#[derive(Debug, Clone)]
+pub struct Notebook {
+    /// Kernel language, lowercased (e.g. "python", "r", "julia")
+    pub language: String,
+    pub cells: Vec<Cell>,
+}
+
+impl Notebook {
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        Self::parse(&bytes).with_context(|| format!("Failed to parse notebook {:?}", path))
+    }
+
+    /// Parse nbformat 4 JSON; outputs are skipped
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let raw: RawNotebook = serde_json::from_slice(bytes)?;
+
+        let language = raw
+            .metadata
+            .kernelspec
+            .and_then(|kernel| kernel.language)
+            .or_else(|| raw.metadata.language_info.and_then(|info| info.name))
+            .map(|language| language.to_lowercase())
+            .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+
+        let cells = raw
+            .cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, cell)| Cell {
+                number: i + 1,
+                kind: match cell.cell_type.as_str() {
+                    "code" => CellKind::Code,
+                    "markdown" => CellKind::Markdown,
+                    _ => CellKind::Other,
+                },
+                source: cell.source.into_text(),
+            })
+            .collect();
+
+        Ok(Self { language, cells })
+    }
+
+    /// Non-empty cells to index and the language of each
+    pub fn indexed_cells(&self, include_markdown: bool) -> impl Iterator<Item = (&Cell, &str)> {
+        self.cells
+            .iter()
+            .filter(|cell| !cell.source.trim().is_empty())
+            .filter_map(move |cell| match cell.kind {
+                CellKind::Code => Some((cell, self.language.as_str())),
+                CellKind::Markdown if include_markdown => Some((cell, "markdown")),
+                _ => None,
+            })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawNotebook {
+    #[serde(default)]
+    metadata: RawMetadata,
+    #[serde(default)]
+    cells: Vec<RawCell>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawMetadata {
+    kernelspec: Option<RawKernelspec>,
+    language_info: Option<RawLanguageInfo>,
+}
+
+#[derive(Deserialize)]
+struct RawKernelspec {
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawLanguageInfo {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawCell {
+    cell_type: String,
+    #[serde(default)]
+    source: RawSource,
+}
+
+/// Cell source: one string, or a list of lines that keep their newlines
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSource {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl Default for RawSource {
+    fn default() -> Self {
+        RawSource::Text(String::new())
+    }
+}
+
+impl RawSource {
+    fn into_text(self) -> String {
+        match self {
+            RawSource::Text(text) => text,
+            RawSource::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cells() {
+        let json = r##"{
+            "metadata": {"kernelspec": {"language": "Python", "name": "python3"}},
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Load\n", "the data"]},
+                {"cell_type": "code", "source": ["import pandas as pd\n", "df = pd.read_csv(path)"],
+                 "outputs": [{"data": {"image/png": "iVBORw0KGgo="}}]},
+                {"cell_type": "code", "source": ""},
+                {"cell_type": "code", "source": "df.head()"}
+            ],
+            "nbformat": 4
+        }"##;
+        let notebook = Notebook::parse(json.as_bytes()).unwrap();
+        assert_eq!(notebook.language, "python");
+
+        let code: Vec<_> = notebook
+            .indexed_cells(false)
+            .map(|(cell, language)| (cell.number, language, cell.source.as_str()))
+            .collect();
+        assert_eq!(
+            code,
+            vec![
+                (2, "python", "import pandas as pd\ndf = pd.read_csv(path)"),
+                (4, "python", "df.head()"),
+            ]
+        );
+        assert_eq!(notebook.indexed_cells(true).count(), 3);
+    }
+
+    #[test]
+    fn test_cell_paths() {
+        let path = cell_path("analysis/eda.ipynb", 12);
+        assert_eq!(path, "analysis/eda.ipynb#cell-12");
+        assert_eq!(split_cell_path(&path), Some(("analysis/eda.ipynb", 12)));
+        assert_eq!(split_cell_path("src/lib.rs"), None);
+    }
+}
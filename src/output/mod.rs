@@ -6,11 +6,33 @@
 use anyhow::Result;
 use colored::*;
 use syntect::easy::HighlightLines;
-use syntect::highlighting::ThemeSet;
+use syntect::highlighting::{Style, ThemeSet};
 use syntect::parsing::SyntaxSet;
 use syntect::util::as_24_bit_terminal_escaped;
 
+use crate::archive;
 use crate::indexer::SearchResult;
+use crate::notebook;
+
+/// A result path for the terminal; archive members show the archive dimmed,
+/// notebook cells the cell number
+pub fn styled_path(path: &str) -> String {
+    if let Some((notebook_path, number)) = notebook::split_cell_path(path) {
+        return format!(
+            "{}{}",
+            notebook_path.blue().bold(),
+            format!("{}{}", notebook::CELL_SEPARATOR, number).cyan()
+        );
+    }
+    match archive::split_member_path(path) {
+        Some((archive_path, member)) => format!(
+            "{}{}",
+            format!("{}{}", archive_path, archive::MEMBER_SEPARATOR).dimmed(),
+            member.blue().bold()
+        ),
+        None => path.blue().bold().to_string(),
+    }
+}
 
 /// Handles formatting and printing search results
 pub struct ResultPrinter {
@@ -54,18 +76,25 @@ impl ResultPrinter {
         println!(
             "{} {} {} {}",
             format!("[{}]", index).cyan().bold(),
-            result.file_path.blue().bold(),
+            styled_path(&result.file_path),
             "·".dimmed(),
             format!("score: {:.2}", result.score).dimmed()
         );
 
         // Language badge
         println!("    {} {}", "language:".dimmed(), result.language.magenta());
+        if let Some(revision) = &result.revision {
+            println!("    {} {}", "revision:".dimmed(), &revision[..revision.len().min(10)]);
+        }
+        if let Some(encoding) = &result.encoding {
+            println!("    {} {}", "encoding:".dimmed(), encoding);
+        }
 
-        // Get syntax for highlighting
-        let syntax = self
-            .ps
-            .find_syntax_by_extension(&result.language)
+        // Get syntax for highlighting (the registry knows each language's extension)
+        let syntax = crate::lang::registry()
+            .highlight_extension(&result.language)
+            .and_then(|ext| self.ps.find_syntax_by_extension(ext))
+            .or_else(|| self.ps.find_syntax_by_token(&result.language))
             .or_else(|| self.ps.find_syntax_by_extension("txt"))
             .unwrap_or_else(|| self.ps.find_syntax_plain_text());
 
@@ -75,7 +104,7 @@ impl ResultPrinter {
         // Print matching lines with context
         let lines: Vec<&str> = result.content.lines().collect();
 
-        for (line_num, _line_content) in &result.matching_lines {
+        for (match_index, (line_num, _line_content)) in result.matching_lines.iter().enumerate() {
             let start = line_num.saturating_sub(self.context_lines + 1);
             let end = (*line_num + self.context_lines).min(lines.len());
 
@@ -93,9 +122,16 @@ impl ResultPrinter {
                     format!("{:>4} │ ", line_number).dimmed()
                 };
 
-                // Syntax highlight the code
+                // Syntax highlight the code, underlining exact matches (--regex)
+                let spans = result
+                    .match_spans
+                    .get(match_index)
+                    .filter(|_| line_number == *line_num);
                 if let Ok(ranges) = highlighter.highlight_line(line, &self.ps) {
-                    let escaped = as_24_bit_terminal_escaped(&ranges[..], false);
+                    let escaped = match spans {
+                        Some(spans) => escape_with_spans(&ranges, spans),
+                        None => as_24_bit_terminal_escaped(&ranges[..], false),
+                    };
                     println!("    {}{}\x1b[0m", prefix, escaped);
                 } else {
                     println!("    {}{}", prefix, line);
@@ -107,3 +143,36 @@ impl ResultPrinter {
         Ok(())
     }
 }
+
+/// Terminal-escape highlighted regions of a line, underlining the byte ranges in `spans`
+fn escape_with_spans(regions: &[(Style, &str)], spans: &[(usize, usize)]) -> String {
+    let mut escaped = String::new();
+    let mut offset = 0;
+
+    for &(style, text) in regions {
+        let end = offset + text.len();
+
+        // Cut the region wherever a match starts or ends
+        let mut cuts: Vec<usize> = spans
+            .iter()
+            .flat_map(|&(start, end)| [start, end])
+            .filter(|&cut| cut > offset && cut < end)
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        let mut start = offset;
+        for cut in cuts.into_iter().chain([end]) {
+            let piece =
+                as_24_bit_terminal_escaped(&[(style, &text[start - offset..cut - offset])], false);
+            if spans.iter().any(|&(s, e)| s <= start && start < e) {
+                escaped.push_str(&format!("\x1b[4m{}\x1b[24m", piece));
+            } else {
+                escaped.push_str(&piece);
+            }
+            start = cut;
+        }
+        offset = end;
+    }
+    escaped
+}
@@ -14,6 +14,8 @@ use std::collections::HashMap;
 #[derive(Debug, Clone)]
 pub struct RankedResult {
     pub file_path: String,
+    /// Chunk identity (`path:start-end`); results are fused per chunk when set, else per file
+    pub chunk_id: Option<String>,
     pub score: f32,
     pub source: SearchSource,
     pub start_line: usize,
@@ -22,6 +24,15 @@ pub struct RankedResult {
     pub name: Option<String>,
 }
 
+impl RankedResult {
+    /// Key identifying the same hit across result lists
+    fn fusion_key(&self) -> String {
+        self.chunk_id
+            .clone()
+            .unwrap_or_else(|| self.file_path.clone())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchSource {
     Lexical,  // BM25/Tantivy
@@ -88,7 +99,7 @@ impl HybridRanker {
         // Process lexical results
         for (rank, result) in lexical_results.into_iter().enumerate() {
             let rrf_score = 1.0 / (k + rank as f32 + 1.0);
-            let key = result.file_path.clone();
+            let key = result.fusion_key();
 
             scores
                 .entry(key)
@@ -104,7 +115,7 @@ impl HybridRanker {
         // Process semantic results
         for (rank, result) in semantic_results.into_iter().enumerate() {
             let rrf_score = 1.0 / (k + rank as f32 + 1.0);
-            let key = result.file_path.clone();
+            let key = result.fusion_key();
 
             scores
                 .entry(key)
@@ -150,7 +161,7 @@ impl HybridRanker {
 
         // Process lexical
         for result in normalized_lexical {
-            let key = result.file_path.clone();
+            let key = result.fusion_key();
             let weighted = result.score * self.config.alpha;
 
             scores
@@ -166,7 +177,7 @@ impl HybridRanker {
 
         // Process semantic
         for result in normalized_semantic {
-            let key = result.file_path.clone();
+            let key = result.fusion_key();
             let weighted = result.score * (1.0 - self.config.alpha);
 
             scores
@@ -270,4 +281,26 @@ mod tests {
         // b.rs should be first since it appears in both
         assert!(results[0].file_path == "b.rs" || results[1].file_path == "b.rs");
     }
+
+    #[test]
+    fn test_rrf_fusion_by_chunk() {
+        let ranker = HybridRanker::new(HybridConfig::default());
+
+        let chunk = |id: &str, source| RankedResult {
+            chunk_id: Some(id.to_string()),
+            ..make_result("a.rs", 1.0, source)
+        };
+
+        let lexical = vec![
+            chunk("a.rs:1-10", SearchSource::Lexical),
+            chunk("a.rs:20-30", SearchSource::Lexical),
+        ];
+        let semantic = vec![chunk("a.rs:20-30", SearchSource::Semantic)];
+
+        let results = ranker.fuse(lexical, semantic, 10);
+
+        // Chunks of the same file stay separate; the shared chunk ranks first
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].chunk_id.as_deref(), Some("a.rs:20-30"));
+    }
 }
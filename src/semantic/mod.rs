@@ -6,12 +6,16 @@
 //! 3. VectorStore: Store and search embeddings efficiently
 
 use anyhow::Result;
-use std::path::Path;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-use crate::chunker::{Chunker, CodeChunk};
-use crate::embedder::Embedder;
+use crate::chunker::{Chunker, CodeChunk, Language};
+use crate::embedder::{self, Embedder};
+use crate::filter::SearchFilter;
 use crate::vector_store::{ChunkMetadata, VectorStore};
+use crate::walk::resolve_threads;
 
 /// Statistics from semantic indexing
 #[derive(Debug, Default)]
@@ -22,6 +26,15 @@ pub struct SemanticIndexStats {
     pub duration_secs: f64,
 }
 
+/// A file, archive member or notebook cell to embed
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Workspace path, as shown in results
+    pub path: PathBuf,
+    pub language: Language,
+    pub content: String,
+}
+
 /// Semantic search result
 #[derive(Debug, Clone)]
 pub struct SemanticResult {
@@ -34,28 +47,131 @@ pub struct SemanticResult {
     pub similarity_score: f32,
 }
 
+/// Contents of a semantic index on disk (`SemanticIndexer::summary`)
+#[derive(Debug, Default, Serialize)]
+pub struct SemanticSummary {
+    pub model: String,
+    pub dimension: usize,
+    pub vectors: usize,
+    /// Live metadata entries (should equal `vectors`)
+    pub chunks: usize,
+    pub size_bytes: u64,
+    pub chunks_by_type: BTreeMap<String, usize>,
+    pub chunks_by_language: BTreeMap<String, usize>,
+    /// Why the index can't be used, if it can't
+    pub problem: Option<String>,
+}
+
 /// Combined semantic indexer
 pub struct SemanticIndexer {
     chunker: Chunker,
     embedder: Option<Embedder>,
     vector_store: Option<VectorStore>,
     index_path: std::path::PathBuf,
+    /// Worker threads for chunking (0 = one per core)
+    threads: usize,
 }
 
 impl SemanticIndexer {
-    /// Create a new semantic indexer
-    pub fn new(base_path: &Path) -> Result<Self> {
-        let index_path = base_path.join("semantic");
+    /// Create a semantic indexer for the index directory at `index_path`
+    pub fn new(index_path: &Path) -> Result<Self> {
+        let index_path = index_path.to_path_buf();
         std::fs::create_dir_all(&index_path)?;
 
+        if let Some(reason) = VectorStore::discard_incompatible(
+            &index_path,
+            embedder::MODEL_NAME,
+            embedder::DIMENSION,
+        )? {
+            tracing::warn!(
+                "Discarded the semantic index ({}). Run `seekr index --semantic` to rebuild it.",
+                reason
+            );
+        }
+
         Ok(Self {
             chunker: Chunker::default(),
             embedder: None,
             vector_store: None,
             index_path,
+            threads: 0,
+        })
+    }
+
+    /// Open the semantic index at `index_path` for searching, without modifying it
+    pub fn open(index_path: &Path) -> Result<Self> {
+        if let Some(reason) =
+            VectorStore::incompatibility(index_path, embedder::MODEL_NAME, embedder::DIMENSION)
+        {
+            anyhow::bail!(
+                "The semantic index can't be used ({}). Run `seekr index --semantic` to rebuild it.",
+                reason
+            );
+        }
+
+        Ok(Self {
+            chunker: Chunker::default(),
+            embedder: None,
+            vector_store: None,
+            index_path: index_path.to_path_buf(),
+            threads: 0,
         })
     }
 
+    /// Describe the semantic index at `index_path` without modifying it
+    ///
+    /// Returns None when there is no semantic index.
+    pub fn summary(index_path: &Path) -> Option<SemanticSummary> {
+        let files = ["vectors.usearch", "metadata.json"].map(|file| index_path.join(file));
+        if !files.iter().any(|file| file.exists()) {
+            return None;
+        }
+
+        let mut summary = SemanticSummary {
+            model: embedder::MODEL_NAME.to_string(),
+            dimension: embedder::DIMENSION,
+            size_bytes: files
+                .iter()
+                .filter_map(|file| std::fs::metadata(file).ok())
+                .map(|m| m.len())
+                .sum(),
+            ..Default::default()
+        };
+        if let Some(reason) =
+            VectorStore::incompatibility(index_path, embedder::MODEL_NAME, embedder::DIMENSION)
+        {
+            summary.problem = Some(reason);
+            return Some(summary);
+        }
+
+        match VectorStore::new(index_path, embedder::MODEL_NAME, embedder::DIMENSION) {
+            Ok(store) => {
+                summary.vectors = store.vector_count();
+                for chunk in store.chunks() {
+                    summary.chunks += 1;
+                    let by_type = summary.chunks_by_type.entry(chunk.chunk_type.clone());
+                    *by_type.or_default() += 1;
+                    let by_language = summary.chunks_by_language.entry(chunk.language.clone());
+                    *by_language.or_default() += 1;
+                }
+                if summary.vectors != summary.chunks {
+                    summary.problem = Some(format!(
+                        "{} vectors but {} metadata entries",
+                        summary.vectors, summary.chunks
+                    ));
+                }
+            }
+            Err(e) => summary.problem = Some(format!("cannot be loaded: {:#}", e)),
+        }
+        Some(summary)
+    }
+
+    /// Set the number of worker threads for chunking (0 = one per core)
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
     /// Initialize the embedder (lazy loading for faster startup)
     fn ensure_embedder(&mut self) -> Result<&Embedder> {
         if self.embedder.is_none() {
@@ -67,39 +183,74 @@ impl SemanticIndexer {
     /// Initialize the vector store
     fn ensure_vector_store(&mut self) -> Result<&mut VectorStore> {
         if self.vector_store.is_none() {
-            let dimension = 384; // BGE-small dimension
-            self.vector_store = Some(VectorStore::new(&self.index_path, dimension)?);
+            self.vector_store = Some(VectorStore::new(
+                &self.index_path,
+                embedder::MODEL_NAME,
+                embedder::DIMENSION,
+            )?);
         }
         Ok(self.vector_store.as_mut().unwrap())
     }
 
     /// Index all files from specified paths
-    pub fn index_files<P: AsRef<Path>>(
-        &mut self,
-        files: &[(P, String)],
-    ) -> Result<SemanticIndexStats> {
+    pub fn index_files(&mut self, files: &[SourceFile]) -> Result<SemanticIndexStats> {
         let start = Instant::now();
         let mut stats = SemanticIndexStats::default();
 
-        // Collect all chunks first
+        // Collect all chunks first; parsing is CPU-bound, so split files across threads
         let mut all_chunks: Vec<CodeChunk> = Vec::new();
+        let per_thread = files.len().div_ceil(resolve_threads(self.threads)).max(1);
+        let chunker = &self.chunker;
 
-        for (path, content) in files {
-            let path = path.as_ref();
-            match self.chunker.chunk_file(path, content) {
-                Ok(chunks) => {
-                    stats.files_processed += 1;
-                    all_chunks.extend(chunks);
-                }
-                Err(e) => {
-                    tracing::debug!("Failed to chunk {:?}: {}", path, e);
-                }
-            }
+        let results: Vec<(usize, Vec<CodeChunk>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = files
+                .chunks(per_thread)
+                .map(|slice| {
+                    scope.spawn(move || {
+                        let mut processed = 0;
+                        let mut chunks = Vec::new();
+                        for file in slice {
+                            let path = file.path.as_path();
+                            match chunker.chunk_file(path, &file.content, file.language) {
+                                Ok(file_chunks) => {
+                                    processed += 1;
+                                    chunks.extend(file_chunks);
+                                }
+                                Err(e) => {
+                                    tracing::debug!("Failed to chunk {:?}: {}", path, e);
+                                }
+                            }
+                        }
+                        (processed, chunks)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("chunker thread panicked"))
+                .collect()
+        });
+
+        for (processed, chunks) in results {
+            stats.files_processed += processed;
+            all_chunks.extend(chunks);
         }
 
         stats.chunks_created = all_chunks.len();
 
+        // Drop vectors from a previous run so re-indexed files are replaced, not duplicated
+        if self.index_exists() {
+            let store = self.ensure_vector_store()?;
+            for file in files {
+                store.remove_file(&file.path.to_string_lossy())?;
+            }
+        }
+
         if all_chunks.is_empty() {
+            if let Some(store) = &self.vector_store {
+                store.save()?;
+            }
             stats.duration_secs = start.elapsed().as_secs_f64();
             return Ok(stats);
         }
@@ -112,7 +263,7 @@ impl SemanticIndexer {
 
         // Process in batches of 32 to limit memory usage
         const BATCH_SIZE: usize = 32;
-        let total_batches = (all_chunks.len() + BATCH_SIZE - 1) / BATCH_SIZE;
+        let total_batches = all_chunks.len().div_ceil(BATCH_SIZE);
 
         for (batch_idx, chunk_batch) in all_chunks.chunks(BATCH_SIZE).enumerate() {
             // Progress indicator
@@ -166,8 +317,29 @@ impl SemanticIndexer {
         Ok(stats)
     }
 
+    /// Remove all chunks of the given files from the semantic index
+    pub fn remove_files(&mut self, file_paths: &[String]) -> Result<usize> {
+        if !self.index_exists() || file_paths.is_empty() {
+            return Ok(0);
+        }
+
+        let store = self.ensure_vector_store()?;
+        let mut removed = 0;
+        for file_path in file_paths {
+            removed += store.remove_file(file_path)?;
+        }
+        store.save()?;
+
+        Ok(removed)
+    }
+
     /// Search for semantically similar code
-    pub fn search(&mut self, query: &str, limit: usize) -> Result<Vec<SemanticResult>> {
+    pub fn search(
+        &mut self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SemanticResult>> {
         self.ensure_embedder()?;
         self.ensure_vector_store()?;
 
@@ -178,7 +350,9 @@ impl SemanticIndexer {
         let query_embedding = embedder.embed_one(query)?;
 
         // Search vector store
-        let results = store.search(&query_embedding, limit)?;
+        let results = store.search(&query_embedding, limit, |m| {
+            filter.matches(&m.file_path, &m.language)
+        })?;
 
         Ok(results
             .into_iter()
@@ -0,0 +1,128 @@
+//! Workspace report for `seekr status`
+//!
+//! Gathers what is indexed (per language, lexical and semantic), how the
+//! index compares with the files on disk, and when it was last updated.
+//! The report serializes as-is for `seekr status --json`.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::cache::{FileCache, FileStatus};
+use crate::classify::FileClassifier;
+use crate::indexer::{self, Candidate, Indexer, LanguageStats};
+use crate::semantic::{SemanticIndexer, SemanticSummary};
+use crate::workspace::{Root, Workspace, WorkspaceRegistry};
+
+/// Files that changed on disk since the last index run
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct PendingChanges {
+    /// Indexed files whose contents changed
+    pub stale: usize,
+    /// Indexable files that are not in the index yet
+    pub new: usize,
+    /// Indexed files that no longer exist
+    pub deleted: usize,
+}
+
+impl PendingChanges {
+    pub fn is_empty(&self) -> bool {
+        self.stale == 0 && self.new == 0 && self.deleted == 0
+    }
+}
+
+/// Everything `seekr status` reports about a workspace
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub workspace: PathBuf,
+    /// Every root, main root first
+    pub roots: Vec<Root>,
+    pub index_path: PathBuf,
+    /// Last index run (seconds since epoch)
+    pub last_indexed: Option<u64>,
+    pub documents: u64,
+    pub size_bytes: u64,
+    pub healthy: bool,
+    pub languages: BTreeMap<String, LanguageStats>,
+    pub changes: PendingChanges,
+    /// None when no semantic index was built
+    pub semantic: Option<SemanticSummary>,
+}
+
+/// Build the report for a workspace; fails when it has no index
+pub fn collect(workspace: &Workspace) -> Result<StatusReport> {
+    let index_path = workspace.index_path();
+    let status = Indexer::get_status(&index_path)?;
+
+    // An outdated index can't be opened; `healthy` already says so
+    let languages = Indexer::open(&index_path)
+        .and_then(|indexer| indexer.language_stats())
+        .unwrap_or_default();
+
+    let last_indexed = WorkspaceRegistry::load()?
+        .entries()
+        .iter()
+        .find(|entry| entry.root == workspace.root())
+        .map(|entry| entry.last_indexed);
+
+    Ok(StatusReport {
+        workspace: workspace.root().to_path_buf(),
+        roots: workspace.roots()?,
+        last_indexed,
+        documents: status.num_docs,
+        size_bytes: status.size_bytes,
+        healthy: status.healthy,
+        languages,
+        changes: pending_changes(workspace)?,
+        semantic: SemanticIndexer::summary(&workspace.semantic_path()),
+        index_path,
+    })
+}
+
+/// Compare the file cache with the files on disk, as the next `seekr index` would
+fn pending_changes(workspace: &Workspace) -> Result<PendingChanges> {
+    let roots = workspace.roots()?;
+    let cache = FileCache::load(&workspace.index_path())?;
+
+    let stale = AtomicUsize::new(0);
+    let new = AtomicUsize::new(0);
+    let seen: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+
+    for root in &roots {
+        let classifier = FileClassifier::for_root(&root.path)?;
+        root.walker(&roots).for_each_file(|path| {
+            seen.lock()
+                .unwrap()
+                .insert(path.to_string_lossy().to_string());
+            let candidate = Candidate::of(path);
+            if !candidate.is_indexable() {
+                return Ok(());
+            }
+
+            match cache.check_file(path) {
+                FileStatus::Modified => {
+                    stale.fetch_add(1, Ordering::Relaxed);
+                }
+                // Binary, generated and oversized files would be skipped, not indexed
+                FileStatus::New
+                    if candidate == Candidate::Container
+                        || indexer::read_source(&classifier, root, path).is_ok() =>
+                {
+                    new.fetch_add(1, Ordering::Relaxed);
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+    }
+
+    let seen = seen.into_inner().unwrap();
+    Ok(PendingChanges {
+        stale: stale.into_inner(),
+        new: new.into_inner(),
+        deleted: cache.paths().filter(|path| !seen.contains(*path)).count(),
+    })
+}
@@ -5,13 +5,25 @@
 //! - Supports multiple distance metrics (cosine, L2)
 //! - Memory-mapped for efficient large-scale search
 //! - Native Rust bindings
+//!
+//! Chunk metadata is stored in `metadata.json` together with a format
+//! version and the embedding model. Vectors from another model or format are
+//! meaningless to the current one, so such a store is discarded and rebuilt.
 
 use anyhow::{Context, Result};
+use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 use usearch::{new_index, Index, IndexOptions, MetricKind, ScalarKind};
 
+use crate::archive;
+use crate::lock;
+use crate::notebook;
+
+/// Format version of `metadata.json`; bump whenever `ChunkMetadata` or the embedded text changes
+pub const METADATA_VERSION: u32 = 1;
+
 /// Metadata stored alongside each vector
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkMetadata {
@@ -24,17 +36,71 @@ pub struct ChunkMetadata {
     pub content_preview: String, // First 200 chars for display
 }
 
+/// Contents of `metadata.json` (`C` = `IgnoredAny` reads only the header)
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredMetadata<C> {
+    version: u32,
+    model: String,
+    dimension: usize,
+    chunks: C,
+}
+
 /// Vector store for semantic search
 pub struct VectorStore {
     index: Index,
     index_path: PathBuf,
     metadata_path: PathBuf,
-    metadata: Vec<ChunkMetadata>,
+    model: String,
+    /// Metadata by vector key; removed entries leave a `None` tombstone
+    metadata: Vec<Option<ChunkMetadata>>,
 }
 
 impl VectorStore {
+    /// Why a store was written in another format or by another model, if it was
+    ///
+    /// Metadata from before versioning is migrated on load, so it counts as compatible.
+    pub fn incompatibility(base_path: &Path, model: &str, dimension: usize) -> Option<String> {
+        let data = fs::read_to_string(base_path.join("metadata.json")).ok()?;
+
+        match serde_json::from_str::<StoredMetadata<IgnoredAny>>(&data) {
+            Ok(header) if header.version != METADATA_VERSION => Some(format!(
+                "format v{}, expected v{}",
+                header.version, METADATA_VERSION
+            )),
+            Ok(header) if header.model != model || header.dimension != dimension => Some(format!(
+                "built with {} ({} dimensions), now using {} ({} dimensions)",
+                header.model, header.dimension, model, dimension
+            )),
+            Ok(_) => None,
+            Err(_) if serde_json::from_str::<Vec<Option<ChunkMetadata>>>(&data).is_ok() => None,
+            Err(_) => Some("unreadable metadata".to_string()),
+        }
+    }
+
+    /// Delete a store written in another format or by another model
+    ///
+    /// Returns why it was discarded, or None if it is usable (or absent).
+    pub fn discard_incompatible(
+        base_path: &Path,
+        model: &str,
+        dimension: usize,
+    ) -> Result<Option<String>> {
+        let reason = Self::incompatibility(base_path, model, dimension);
+        if reason.is_some() {
+            fs::remove_file(base_path.join("metadata.json"))?;
+            let index_path = base_path.join("vectors.usearch");
+            if index_path.exists() {
+                fs::remove_file(index_path)?;
+            }
+        }
+        Ok(reason)
+    }
+
     /// Create or open a vector store at the given path
-    pub fn new(base_path: &Path, dimension: usize) -> Result<Self> {
+    ///
+    /// Call `discard_incompatible` first; this assumes any existing store
+    /// was built by `model`.
+    pub fn new(base_path: &Path, model: &str, dimension: usize) -> Result<Self> {
         let index_path = base_path.join("vectors.usearch");
         let metadata_path = base_path.join("metadata.json");
 
@@ -55,7 +121,11 @@ impl VectorStore {
         // Load existing metadata if present
         let metadata = if metadata_path.exists() {
             let data = fs::read_to_string(&metadata_path)?;
-            serde_json::from_str(&data).unwrap_or_default()
+            match serde_json::from_str::<StoredMetadata<Vec<Option<ChunkMetadata>>>>(&data) {
+                Ok(stored) => stored.chunks,
+                // Before versioning the file was a bare array; it is rewritten on save
+                Err(_) => serde_json::from_str(&data).context("Failed to parse vector metadata")?,
+            }
         } else {
             Vec::new()
         };
@@ -71,10 +141,21 @@ impl VectorStore {
             index,
             index_path,
             metadata_path,
+            model: model.to_string(),
             metadata,
         })
     }
 
+    /// Number of vectors in the index
+    pub fn vector_count(&self) -> usize {
+        self.index.size()
+    }
+
+    /// Metadata of every live chunk
+    pub fn chunks(&self) -> impl Iterator<Item = &ChunkMetadata> {
+        self.metadata.iter().flatten()
+    }
+
     /// Add a vector with its metadata
     pub fn add(&mut self, vector: &[f32], metadata: ChunkMetadata) -> Result<u64> {
         let key = self.metadata.len() as u64;
@@ -92,28 +173,51 @@ impl VectorStore {
             .add(key, vector)
             .context("Failed to add vector to index")?;
 
-        self.metadata.push(metadata);
+        self.metadata.push(Some(metadata));
 
         Ok(key)
     }
 
+    /// Remove every vector belonging to a file (or to the members of an
+    /// archive or cells of a notebook), returning how many were removed
+    pub fn remove_file(&mut self, file_path: &str) -> Result<usize> {
+        let mut removed = 0;
 
+        for (key, slot) in self.metadata.iter_mut().enumerate() {
+            if slot
+                .as_ref()
+                .is_some_and(|m| m.file_path == file_path || source_file(&m.file_path) == file_path)
+            {
+                self.index
+                    .remove(key as u64)
+                    .context("Failed to remove vector from index")?;
+                *slot = None;
+                removed += 1;
+            }
+        }
 
-    /// Search for similar vectors
-    pub fn search(&self, query_vector: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+        Ok(removed)
+    }
+
+    /// Search for similar vectors among chunks accepted by `filter`
+    pub fn search<F>(&self, query_vector: &[f32], limit: usize, filter: F) -> Result<Vec<SearchResult>>
+    where
+        F: Fn(&ChunkMetadata) -> bool,
+    {
         let results = self
             .index
-            .search(query_vector, limit)
+            .filtered_search(query_vector, limit, |key| {
+                matches!(self.metadata.get(key as usize), Some(Some(m)) if filter(m))
+            })
             .context("Failed to search vectors")?;
 
         let mut search_results = Vec::new();
 
         for (key, distance) in results.keys.iter().zip(results.distances.iter()) {
-            let key = *key as usize;
-            if key < self.metadata.len() {
+            if let Some(Some(metadata)) = self.metadata.get(*key as usize) {
                 search_results.push(SearchResult {
                     score: 1.0 - distance, // Convert distance to similarity
-                    metadata: self.metadata[key].clone(),
+                    metadata: metadata.clone(),
                 });
             }
         }
@@ -123,14 +227,21 @@ impl VectorStore {
 
     /// Save the index and metadata to disk
     pub fn save(&self) -> Result<()> {
+        // Both files are written aside and renamed into place so searches never load a partial file
+        let index_tmp = lock::tmp_path_for(&self.index_path);
         self.index
-            .save(self.index_path.to_str().unwrap())
+            .save(index_tmp.to_str().unwrap())
             .context("Failed to save vector index")?;
+        fs::rename(&index_tmp, &self.index_path).context("Failed to replace vector index")?;
 
-        let metadata_json = serde_json::to_string_pretty(&self.metadata)?;
-        fs::write(&self.metadata_path, metadata_json)?;
-
-        Ok(())
+        let stored = StoredMetadata {
+            version: METADATA_VERSION,
+            model: self.model.clone(),
+            dimension: self.index.dimensions(),
+            chunks: &self.metadata,
+        };
+        let metadata_json = serde_json::to_string_pretty(&stored)?;
+        lock::write_atomic(&self.metadata_path, metadata_json)
     }
 }
 
@@ -140,3 +251,9 @@ pub struct SearchResult {
     pub score: f32,
     pub metadata: ChunkMetadata,
 }
+
+/// File on disk a chunk's (possibly virtual) path comes from
+fn source_file(path: &str) -> &str {
+    let path = archive::source_path(path);
+    notebook::split_cell_path(path).map_or(path, |(notebook_path, _)| notebook_path)
+}
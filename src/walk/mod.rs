@@ -0,0 +1,325 @@
+//! Parallel source tree walker shared by lexical and semantic indexing
+//!
+//! Architecture Decision: Files are processed on the walker's own threads
+//! (`ignore`'s parallel walker) instead of collecting paths first, because:
+//! 1. Reading, language detection, chunking and document construction all
+//!    scale with cores, and the walk itself overlaps with that work
+//! 2. The thread count bounds the work in flight; Tantivy's writer queue
+//!    applies back-pressure when indexing falls behind
+//! 3. Every caller agrees on which files belong to a workspace (.gitignore,
+//!    `.seekrignore`, hidden files)
+//!
+//! `.seekrignore` uses gitignore syntax and takes precedence over
+//! `.gitignore`: it can leave out committed paths (fixtures, snapshots,
+//! generated clients) and `!pattern` lines bring back gitignored or hidden
+//! ones (e.g. `!generated/api/`).
+
+use anyhow::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{WalkBuilder, WalkState};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Project-level ignore file, in gitignore syntax
+pub const IGNORE_FILE: &str = ".seekrignore";
+
+/// Walks the files of a workspace on a pool of threads
+#[derive(Debug, Clone)]
+pub struct SourceWalker {
+    root: PathBuf,
+    threads: usize,
+    /// Directories left out of the walk
+    skip_dirs: Vec<PathBuf>,
+    /// Honour .gitignore, .seekrignore, .ignore and git excludes
+    ignore_files: bool,
+    /// Leave out hidden files and directories
+    skip_hidden: bool,
+}
+
+impl SourceWalker {
+    /// Walker over `root`, using one thread per core
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            threads: 0,
+            skip_dirs: Vec::new(),
+            ignore_files: true,
+            skip_hidden: true,
+        }
+    }
+
+    /// Leave these directories (and everything below them) out of the walk
+    pub fn skip_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.skip_dirs = dirs;
+        self
+    }
+
+    /// Whether ignore files apply (on by default)
+    ///
+    /// Dependency sources sit in directories the workspace ignores (e.g.
+    /// `node_modules`), so they are walked without them.
+    pub fn ignore_files(mut self, respect: bool) -> Self {
+        self.ignore_files = respect;
+        self
+    }
+
+    /// Whether hidden files and directories are left out (on by default)
+    pub fn skip_hidden(mut self, skip: bool) -> Self {
+        self.skip_hidden = skip;
+        self
+    }
+
+    /// Set the number of worker threads (0 = one per core)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Resolved number of worker threads
+    pub fn num_threads(&self) -> usize {
+        resolve_threads(self.threads)
+    }
+
+    /// Call `visit` for every file, in parallel and in no particular order
+    ///
+    /// The first error stops the walk and is returned.
+    pub fn for_each_file<F>(&self, visit: F) -> Result<()>
+    where
+        F: Fn(&Path) -> Result<()> + Sync,
+    {
+        let first_error: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+        let skip_dirs = self.skip_dirs.clone();
+
+        let mut builder = WalkBuilder::new(&self.root);
+        builder
+            .hidden(self.skip_hidden)
+            .parents(self.ignore_files)
+            .ignore(self.ignore_files)
+            .git_ignore(self.ignore_files)
+            .git_global(self.ignore_files)
+            .git_exclude(self.ignore_files)
+            .threads(self.num_threads())
+            .filter_entry(move |entry| !skip_dirs.iter().any(|dir| entry.path() == dir));
+        if self.ignore_files {
+            builder.add_custom_ignore_filename(IGNORE_FILE);
+        }
+
+        builder.build_parallel().run(|| {
+            Box::new(|entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                let path = entry.path();
+                if !path.is_file() {
+                    return WalkState::Continue;
+                }
+
+                match visit(path) {
+                    Ok(()) => WalkState::Continue,
+                    Err(e) => {
+                        first_error.lock().unwrap().get_or_insert(e);
+                        WalkState::Quit
+                    }
+                }
+            })
+        });
+
+        match first_error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The walker's ignore rules, for checking single paths (e.g. from file events)
+///
+/// Every ignore file from the filesystem root down to the path applies, with
+/// the walker's precedence: within each kind the deepest file with a matching
+/// pattern decides, and `.seekrignore` beats `.ignore`, which beats
+/// `.gitignore`, `.git/info/exclude` and the global excludes file. Git's
+/// files only apply inside a repository. Ignore files are read once per
+/// directory; build new rules when one changes.
+#[derive(Debug)]
+pub struct IgnoreRules {
+    roots: Vec<PathBuf>,
+    global: Gitignore,
+    dirs: Mutex<HashMap<PathBuf, Arc<DirIgnores>>>,
+}
+
+/// The ignore files of one directory
+#[derive(Debug)]
+struct DirIgnores {
+    seekrignore: Gitignore,
+    ignore: Gitignore,
+    gitignore: Gitignore,
+    /// `.git/info/exclude`, when the directory is a repository root
+    exclude: Gitignore,
+    is_repository: bool,
+}
+
+impl DirIgnores {
+    fn load(dir: &Path) -> Self {
+        let read = |path: PathBuf| {
+            let mut builder = GitignoreBuilder::new(dir);
+            if path.is_file() {
+                if let Some(e) = builder.add(&path) {
+                    tracing::warn!("Ignoring invalid patterns in {:?}: {}", path, e);
+                }
+            }
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        };
+        let git_dir = dir.join(".git");
+        Self {
+            seekrignore: read(dir.join(IGNORE_FILE)),
+            ignore: read(dir.join(".ignore")),
+            gitignore: read(dir.join(".gitignore")),
+            exclude: read(git_dir.join("info").join("exclude")),
+            is_repository: git_dir.exists(),
+        }
+    }
+}
+
+impl IgnoreRules {
+    pub fn for_roots<'a>(roots: impl IntoIterator<Item = &'a Path>) -> Self {
+        Self {
+            roots: roots.into_iter().map(Path::to_path_buf).collect(),
+            global: Gitignore::global().0,
+            dirs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the walker would leave `path` out
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        // The innermost root containing the path decides
+        let Some(root) = self
+            .roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+        else {
+            return true;
+        };
+        let Ok(relative) = path.strip_prefix(root) else {
+            return true;
+        };
+
+        // Like the walker, check every directory on the way down, then the path itself
+        let components: Vec<_> = relative.components().collect();
+        let mut entry = root.clone();
+        for (i, component) in components.iter().enumerate() {
+            entry.push(component);
+            let is_dir = i + 1 < components.len() || path.is_dir();
+            match self.matched(&entry, is_dir) {
+                Some(true) => return true,
+                Some(false) => {}
+                // Hidden paths are left out unless an ignore file whitelists them
+                None if component.as_os_str().to_string_lossy().starts_with('.') => return true,
+                None => {}
+            }
+        }
+        false
+    }
+
+    /// Whether the ignore files of `path`'s directories ignore it (Some(true)),
+    /// whitelist it (Some(false)) or say nothing about it
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let ancestors: Vec<Arc<DirIgnores>> =
+            path.ancestors().skip(1).map(|dir| self.dir(dir)).collect();
+        let in_repository = ancestors.iter().any(|dir| dir.is_repository);
+
+        // First match from the deepest directory up; git's files stop at the repository root
+        let first_match = |rules: fn(&DirIgnores) -> &Gitignore, git: bool| {
+            for dir in &ancestors {
+                let matched = rules(dir).matched(path, is_dir);
+                if !matched.is_none() {
+                    return Some(matched.is_ignore());
+                }
+                if git && dir.is_repository {
+                    break;
+                }
+            }
+            None
+        };
+
+        first_match(|dir| &dir.seekrignore, false)
+            .or_else(|| first_match(|dir| &dir.ignore, false))
+            .or_else(|| {
+                if !in_repository {
+                    return None;
+                }
+                first_match(|dir| &dir.gitignore, true)
+                    .or_else(|| first_match(|dir| &dir.exclude, true))
+                    .or_else(|| {
+                        let matched = self.global.matched(path, is_dir);
+                        (!matched.is_none()).then(|| matched.is_ignore())
+                    })
+            })
+    }
+
+    fn dir(&self, dir: &Path) -> Arc<DirIgnores> {
+        self.dirs
+            .lock()
+            .unwrap()
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| Arc::new(DirIgnores::load(dir)))
+            .clone()
+    }
+}
+
+/// Whether a file is an ignore file whose changes affect which files are walked
+pub fn is_ignore_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == IGNORE_FILE || name == ".gitignore" || name == ".ignore")
+}
+
+/// Resolve a `--threads` value (0 = one per core)
+pub fn resolve_threads(threads: usize) -> usize {
+    if threads > 0 {
+        threads
+    } else {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_ignore_rules() {
+        let root = std::env::temp_dir().join(format!("seekr-ignore-rules-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        fs::create_dir_all(root.join(".git")).unwrap();
+        write(".gitignore", "gen/\n*.log\n");
+        write(IGNORE_FILE, "!gen/\ngen/*\n!gen/api/\n");
+        write("lib/.seekrignore", "fixtures/\n");
+        write("lib/.gitignore", "!keep.log\n");
+
+        let rules = IgnoreRules::for_roots([root.as_path()]);
+        let ignored = |path: &str| rules.is_ignored(&root.join(path));
+
+        // A .seekrignore negation re-includes part of a gitignored directory
+        assert!(!ignored("gen/api/client.rs"));
+        assert!(ignored("gen/models.rs"));
+
+        // Nested ignore files apply below their directory, and only there
+        assert!(ignored("lib/fixtures/case.rs"));
+        assert!(!ignored("fixtures/case.rs"));
+        assert!(!ignored("lib/keep.log"));
+        assert!(ignored("debug.log"));
+
+        assert!(ignored(".hidden/file.rs"));
+        assert!(!ignored("src/main.rs"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
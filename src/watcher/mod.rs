@@ -12,6 +12,10 @@ use std::sync::mpsc;
 use std::time::Duration;
 
 use crate::indexer::Indexer;
+use crate::lock::{IndexBusy, WorkspaceLock};
+use crate::semantic::SemanticIndexer;
+use crate::walk::{self, IgnoreRules};
+use crate::workspace::Workspace;
 
 /// File system watcher that triggers re-indexing on changes
 pub struct FileWatcher {
@@ -26,8 +30,12 @@ impl Default for FileWatcher {
 
 impl FileWatcher {
 
-    /// Watch a directory and re-index on changes
-    pub fn watch(&self, path: &Path) -> Result<()> {
+    /// Watch a workspace and re-index on changes
+    ///
+    /// Every root is watched; roots added while watching are indexed on the
+    /// next change but only watched after a restart.
+    pub fn watch(&self, workspace: &Workspace) -> Result<()> {
+        let roots = workspace.roots()?;
         let (tx, rx) = mpsc::channel();
 
         let mut watcher = RecommendedWatcher::new(
@@ -39,17 +47,34 @@ impl FileWatcher {
             Config::default(),
         )?;
 
-        watcher.watch(path, RecursiveMode::Recursive)?;
+        // Roots nested in another root are covered by its recursive watch
+        for root in &roots {
+            let nested = roots
+                .iter()
+                .any(|other| other.path != root.path && root.path.starts_with(&other.path));
+            if !nested {
+                watcher.watch(&root.path, RecursiveMode::Recursive)?;
+            }
+        }
 
         println!(
             "🔍 Watching {} for changes... (Ctrl+C to stop)",
-            path.display()
+            workspace.root().display()
         );
+        for root in roots.iter().filter(|root| !root.main) {
+            println!("   + @{} ({})", root.label, root.path.display());
+        }
         println!("   Debounce: {}ms\n", self.debounce_ms);
 
+        // Same ignore rules as the indexer's walk; reloaded when an ignore file changes
+        let root_paths = || roots.iter().map(|root| root.path.as_path());
+        let mut ignore_rules = IgnoreRules::for_roots(root_paths());
+
         let mut pending_files: HashSet<String> = HashSet::new();
         let mut last_index_time = std::time::Instant::now();
         let debounce_duration = Duration::from_millis(self.debounce_ms);
+        // Another writer holds the index; changes are retried quietly until it is done
+        let mut waiting_for_lock = false;
 
         loop {
             // Collect events with timeout
@@ -59,8 +84,14 @@ impl FileWatcher {
                     match event.kind {
                         EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
                             for path in event.paths {
-                                // Skip non-code files and hidden directories
-                                if Self::is_indexable(&path) {
+                                // Files may enter or leave the workspace; reindex to find out
+                                if walk::is_ignore_file(&path) {
+                                    ignore_rules = IgnoreRules::for_roots(root_paths());
+                                    pending_files.insert(path.display().to_string());
+                                    continue;
+                                }
+                                // Skip non-code files and ignored or hidden paths
+                                if Self::is_indexable(&path) && !ignore_rules.is_ignored(&path) {
                                     pending_files.insert(path.display().to_string());
                                 }
                             }
@@ -72,22 +103,34 @@ impl FileWatcher {
                     // Debounce timeout - process pending changes
                     if !pending_files.is_empty() && last_index_time.elapsed() >= debounce_duration {
                         let count = pending_files.len();
-                        println!("📝 {} file(s) changed, re-indexing...", count);
+                        if !waiting_for_lock {
+                            println!("📝 {} file(s) changed, re-indexing...", count);
+                        }
 
                         // Re-index
-                        match self.reindex(path) {
+                        match self.reindex(workspace) {
                             Ok(stats) => {
                                 println!(
                                     "   ✨ Indexed {} files in {:.2}s\n",
                                     stats.files_indexed, stats.duration_secs
                                 );
                             }
-                            Err(e) => {
-                                println!("   ❌ Index error: {}\n", e);
-                            }
+                            Err(e) => match e.downcast_ref::<IndexBusy>() {
+                                Some(busy) => {
+                                    // Keep the changes and try again after the next debounce period
+                                    if !waiting_for_lock {
+                                        println!("   ⏳ Waiting for {} to finish...", busy.holder);
+                                        waiting_for_lock = true;
+                                    }
+                                    last_index_time = std::time::Instant::now();
+                                    continue;
+                                }
+                                None => println!("   ❌ Index error: {}\n", e),
+                            },
                         }
 
                         pending_files.clear();
+                        waiting_for_lock = false;
                         last_index_time = std::time::Instant::now();
                     }
                 }
@@ -100,51 +143,31 @@ impl FileWatcher {
         Ok(())
     }
 
-    /// Check if a file should be indexed
+    /// Check if a file is of a kind that is indexed
     fn is_indexable(path: &Path) -> bool {
-        // Skip hidden files and directories
-        if path.components().any(|c| {
-            c.as_os_str()
-                .to_str()
-                .map(|s| s.starts_with('.'))
-                .unwrap_or(false)
-        }) {
-            return false;
-        }
-
-        // Check extension
-        match path.extension().and_then(|e| e.to_str()) {
-            Some(ext) => matches!(
-                ext,
-                "rs" | "py"
-                    | "js"
-                    | "jsx"
-                    | "ts"
-                    | "tsx"
-                    | "go"
-                    | "java"
-                    | "c"
-                    | "h"
-                    | "cpp"
-                    | "hpp"
-                    | "rb"
-                    | "md"
-                    | "toml"
-                    | "yaml"
-                    | "json"
-            ),
-            None => false,
-        }
+        // Same decision as the indexer
+        crate::indexer::Candidate::of(path).is_indexable()
     }
 
     /// Perform incremental re-indexing
-    fn reindex(&self, path: &Path) -> Result<crate::indexer::IndexStats> {
+    fn reindex(&self, workspace: &Workspace) -> Result<crate::indexer::IndexStats> {
+        let _lock = WorkspaceLock::acquire(workspace, "seekr watch")?;
+
+        // Checked on the live index: after a format upgrade `Indexer::new`
+        // returns an empty staged one, which must still get chunk documents
+        let chunks = Indexer::has_chunks_at(&workspace.index_path());
+        let mut indexer = Indexer::new(workspace, false)?.with_chunks(chunks);
+
         // Load file cache
-        let home = dirs::home_dir().expect("Could not find home directory");
-        let cache_path = home.join(".seekr");
-        let mut file_cache = crate::cache::FileCache::load(&cache_path)?;
+        let mut file_cache = crate::cache::FileCache::load(indexer.path())?;
+        let stats = indexer.index_directory_incremental(&workspace.roots()?, &mut file_cache)?;
+        indexer.publish()?;
+        workspace.register()?;
+
+        // Keep the semantic index free of deleted files
+        let mut semantic_indexer = SemanticIndexer::new(&workspace.semantic_path())?;
+        semantic_indexer.remove_files(&stats.deleted_files)?;
 
-        let mut indexer = Indexer::new(path, false)?;
-        indexer.index_directory_incremental(path, &mut file_cache)
+        Ok(stats)
     }
 }
@@ -0,0 +1,578 @@
+//! Per-workspace storage layout and registry
+//!
+//! Every indexed root gets its own data directory under
+//! `~/.seekr/workspaces/<id>/`, where `<id>` is derived from the canonical
+//! root path. A registry (`~/.seekr/workspaces.json`) records all known
+//! workspaces so searches can pick the index for the current directory or
+//! its nearest indexed ancestor.
+//!
+//! A workspace can span several roots (`seekr root add`), e.g. a service,
+//! shared libraries and a vendored SDK of a monorepo. Files of the main root
+//! keep their relative paths; files of an added root are indexed as
+//! `@<label>/<relative path>`, so roots come and go without a full reindex.
+//!
+//! Layout of a workspace data directory:
+//! - index.<n>/: Tantivy BM25 index, with `file_cache.json` (file fingerprints
+//!   for incremental indexing) inside it
+//! - semantic.<n>/: Vector embeddings and metadata
+//! - index.current, semantic.current: Name of the live generation of each
+//! - roots.json: Roots added to the workspace besides its main root
+//! - revisions/<commit>/: Tantivy indexes of git revisions (`--rev`)
+//! - history.<n>/, history.current: Tantivy index of commit history (`seekr log-search`)
+//! - deps.<n>/, deps.current: Tantivy index of dependency sources (`seekr index --deps`)
+//!
+//! Full rebuilds go into a new generation (`StagedDir`) while searches keep
+//! using the live one; publishing it swaps the pointer file with an atomic
+//! rename. Without a pointer file, the plain `index/`, `semantic/`,
+//! `history/` and `deps/` directories of older versions are live.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::lock::{self, write_atomic};
+use crate::walk::SourceWalker;
+
+/// File listing the roots added to a workspace
+const ROOTS_FILE: &str = "roots.json";
+
+/// A single indexed workspace and its on-disk data directory
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+    id: String,
+    data_dir: PathBuf,
+}
+
+impl Workspace {
+    /// Get the seekr home directory (~/.seekr)
+    pub fn seekr_home() -> Result<PathBuf> {
+        let home = dirs::home_dir().context("Could not find home directory")?;
+        Ok(home.join(".seekr"))
+    }
+
+    /// Get the workspace for a root directory (it does not need to be indexed yet)
+    pub fn for_root(root: &Path) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Could not resolve workspace path {:?}", root))?;
+        let id = workspace_id(&root);
+        let data_dir = Self::data_dir_for_id(&id)?;
+
+        Ok(Self { root, id, data_dir })
+    }
+
+    /// Data directory of a workspace id (its root does not need to exist)
+    pub fn data_dir_for_id(id: &str) -> Result<PathBuf> {
+        Ok(Self::seekr_home()?.join("workspaces").join(id))
+    }
+
+    /// Find the indexed workspace containing `path` (the nearest indexed ancestor)
+    pub fn discover(path: &Path) -> Result<Self> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Could not resolve path {:?}", path))?;
+        let registry = WorkspaceRegistry::load()?;
+
+        let entry = registry.find_for(&path).with_context(|| {
+            format!(
+                "No index found for {} or any parent directory. Run `seekr index` first.",
+                path.display()
+            )
+        })?;
+
+        Self::for_root(&entry.root)
+    }
+
+    /// Canonical root directory of the workspace
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding all index data for this workspace
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Every root of the workspace, main root first
+    pub fn roots(&self) -> Result<Vec<Root>> {
+        let mut roots = vec![Root {
+            label: sanitized_name(&self.root),
+            path: self.root.clone(),
+            main: true,
+            dependency: false,
+        }];
+        roots.extend(self.added_roots()?);
+        Ok(roots)
+    }
+
+    /// Roots added with `seekr root add`
+    fn added_roots(&self) -> Result<Vec<Root>> {
+        let path = self.data_dir.join(ROOTS_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path).context("Failed to read workspace roots")?;
+        serde_json::from_str(&content).context("Failed to parse workspace roots")
+    }
+
+    /// Add a root, labelled `label` or its directory name
+    ///
+    /// Only records the root; indexing its files is up to the caller.
+    pub fn add_root(&self, path: &Path, label: Option<&str>) -> Result<Root> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Could not resolve root path {:?}", path))?;
+        if !path.is_dir() {
+            anyhow::bail!("{} is not a directory", path.display());
+        }
+        let label = label
+            .map(str::to_string)
+            .unwrap_or_else(|| sanitized_name(&path));
+        if label.is_empty()
+            || !label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            anyhow::bail!(
+                "Invalid root label {:?}: use letters, digits, '-', '_' and '.'",
+                label
+            );
+        }
+
+        let mut added = self.added_roots()?;
+        if path == self.root || added.iter().any(|root| root.path == path) {
+            anyhow::bail!("{} is already a root of this workspace", path.display());
+        }
+        if added.iter().any(|root| root.label == label) {
+            anyhow::bail!(
+                "A root labelled {:?} already exists. Pick another with --label.",
+                label
+            );
+        }
+
+        let root = Root {
+            label,
+            path,
+            main: false,
+            dependency: false,
+        };
+        added.push(root.clone());
+        self.save_roots(&added)?;
+        Ok(root)
+    }
+
+    /// Remove an added root by label, returning it
+    ///
+    /// Only updates the root list; its documents must be purged by the caller.
+    pub fn remove_root(&self, label: &str) -> Result<Root> {
+        let mut added = self.added_roots()?;
+        let position = added
+            .iter()
+            .position(|root| root.label == label)
+            .with_context(|| {
+                format!(
+                    "No added root labelled {:?}. Run `seekr root list` to see the roots.",
+                    label
+                )
+            })?;
+        let root = added.remove(position);
+        self.save_roots(&added)?;
+        Ok(root)
+    }
+
+    fn save_roots(&self, roots: &[Root]) -> Result<()> {
+        fs::create_dir_all(&self.data_dir)?;
+        write_atomic(
+            &self.data_dir.join(ROOTS_FILE),
+            serde_json::to_string_pretty(roots)?,
+        )
+    }
+
+    /// Path of the live Tantivy index for this workspace
+    pub fn index_path(&self) -> PathBuf {
+        live_path(&self.data_dir, INDEX_DIR)
+    }
+
+    /// Path of the live semantic index (vectors and metadata)
+    pub fn semantic_path(&self) -> PathBuf {
+        live_path(&self.data_dir, SEMANTIC_DIR)
+    }
+
+    /// Start a new generation of the Tantivy index for a full rebuild
+    pub fn stage_index(&self) -> Result<StagedDir> {
+        StagedDir::create(&self.data_dir, INDEX_DIR)
+    }
+
+    /// Start a new generation of the semantic index for a full rebuild
+    pub fn stage_semantic(&self) -> Result<StagedDir> {
+        StagedDir::create(&self.data_dir, SEMANTIC_DIR)
+    }
+
+    /// Path of the Tantivy index for a git revision snapshot
+    pub fn revision_index_path(&self, sha: &str) -> PathBuf {
+        self.data_dir.join("revisions").join(sha)
+    }
+
+    /// Path of the live Tantivy index of commit history
+    pub fn history_index_path(&self) -> PathBuf {
+        live_path(&self.data_dir, HISTORY_DIR)
+    }
+
+    /// Start a new generation of the history index for a full rebuild
+    pub fn stage_history(&self) -> Result<StagedDir> {
+        StagedDir::create(&self.data_dir, HISTORY_DIR)
+    }
+
+    /// Path of the live Tantivy index of dependency sources
+    pub fn deps_index_path(&self) -> PathBuf {
+        live_path(&self.data_dir, DEPS_DIR)
+    }
+
+    /// Start a new generation of the dependency index for a full rebuild
+    pub fn stage_deps(&self) -> Result<StagedDir> {
+        StagedDir::create(&self.data_dir, DEPS_DIR)
+    }
+
+    /// Record this workspace in the registry, stamping the index time
+    pub fn register(&self) -> Result<()> {
+        let added_roots = self
+            .added_roots()?
+            .into_iter()
+            .map(|root| root.path)
+            .collect();
+        WorkspaceRegistry::update(|registry| {
+            registry.upsert(WorkspaceEntry {
+                id: self.id.clone(),
+                root: self.root.clone(),
+                added_roots,
+                last_indexed: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+            });
+        })
+    }
+}
+
+const INDEX_DIR: &str = "index";
+const SEMANTIC_DIR: &str = "semantic";
+const HISTORY_DIR: &str = "history";
+const DEPS_DIR: &str = "deps";
+
+/// A new generation of an index directory, built next to the live one
+///
+/// Nothing reads it until `publish` points `<name>.current` at it. Dropping
+/// it unpublished (a failed rebuild) deletes it and leaves the live index alone.
+#[derive(Debug)]
+pub struct StagedDir {
+    data_dir: PathBuf,
+    name: &'static str,
+    generation: String,
+    published: bool,
+}
+
+impl StagedDir {
+    fn create(data_dir: &Path, name: &'static str) -> Result<Self> {
+        fs::create_dir_all(data_dir)?;
+        let live = live_path(data_dir, name);
+
+        // Generations other than the live one are left over from rebuilds that never finished
+        let mut next = 1;
+        for entry in fs::read_dir(data_dir)? {
+            let entry = entry?;
+            let Some(number) = generation_number(&entry.file_name().to_string_lossy(), name) else {
+                continue;
+            };
+            next = next.max(number + 1);
+            if entry.path() != live {
+                fs::remove_dir_all(entry.path())
+                    .with_context(|| format!("Failed to remove {:?}", entry.path()))?;
+            }
+        }
+
+        let staged = Self {
+            data_dir: data_dir.to_path_buf(),
+            name,
+            generation: format!("{}.{}", name, next),
+            published: false,
+        };
+        fs::create_dir_all(staged.path())?;
+        Ok(staged)
+    }
+
+    /// Directory to build the new generation in
+    pub fn path(&self) -> PathBuf {
+        self.data_dir.join(&self.generation)
+    }
+
+    /// Make this generation live, then delete the one it replaces
+    pub fn publish(mut self) -> Result<()> {
+        let previous = live_path(&self.data_dir, self.name);
+
+        // Readers see either the old or the new pointer, never a partial one
+        let pointer = self.data_dir.join(format!("{}.current", self.name));
+        write_atomic(&pointer, &self.generation)
+            .with_context(|| format!("Failed to publish {}", self.generation))?;
+        self.published = true;
+
+        if previous.exists() {
+            if let Err(e) = fs::remove_dir_all(&previous) {
+                tracing::warn!("Failed to remove the previous index {:?}: {}", previous, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StagedDir {
+    fn drop(&mut self) {
+        if !self.published {
+            let _ = fs::remove_dir_all(self.path());
+        }
+    }
+}
+
+/// Live directory of an index: the generation named in `<name>.current`,
+/// or the unversioned `<name>/` of older layouts
+fn live_path(data_dir: &Path, name: &str) -> PathBuf {
+    let pointer = data_dir.join(format!("{}.current", name));
+    match fs::read_to_string(pointer) {
+        Ok(generation) if generation_number(generation.trim(), name).is_some() => {
+            data_dir.join(generation.trim())
+        }
+        _ => data_dir.join(name),
+    }
+}
+
+/// Generation number of a directory named `<name>.<n>`
+fn generation_number(file_name: &str, name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(name)?
+        .strip_prefix('.')?
+        .parse()
+        .ok()
+}
+
+/// A directory indexed as part of a workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Root {
+    pub label: String,
+    pub path: PathBuf,
+    /// The workspace's main root (where it was first indexed); never stored
+    #[serde(skip)]
+    pub main: bool,
+    /// Sources of a dependency (see `deps`), walked without ignore files; never stored
+    #[serde(skip)]
+    pub dependency: bool,
+}
+
+impl Root {
+    /// Path of a file under this root as stored in the index (`@<label>/...`
+    /// for added roots)
+    pub fn workspace_path(&self, file: &Path) -> String {
+        let relative = self.relative_path(file);
+        if self.main {
+            relative
+        } else {
+            format!("@{}/{}", self.label, relative)
+        }
+    }
+
+    /// Path of a file relative to this root
+    pub fn relative_path(&self, file: &Path) -> String {
+        file.strip_prefix(&self.path)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Walker over this root's files, leaving out other roots nested inside it
+    pub fn walker(&self, roots: &[Root]) -> SourceWalker {
+        let nested = roots
+            .iter()
+            .filter(|other| other.path != self.path && other.path.starts_with(&self.path))
+            .map(|other| other.path.clone())
+            .collect();
+        SourceWalker::new(&self.path)
+            .skip_dirs(nested)
+            .ignore_files(!self.dependency)
+    }
+}
+
+/// The root a file belongs to: the innermost root containing it
+pub fn owning_root<'a>(roots: &'a [Root], file: &Path) -> Option<&'a Root> {
+    roots
+        .iter()
+        .filter(|root| file.starts_with(&root.path))
+        .max_by_key(|root| root.path.components().count())
+}
+
+/// A registry entry describing one known workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceEntry {
+    pub id: String,
+    pub root: PathBuf,
+    /// Roots added with `seekr root add`; searches from inside them use this workspace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_roots: Vec<PathBuf>,
+    /// Last time the workspace was indexed (seconds since epoch)
+    pub last_indexed: u64,
+}
+
+/// Registry of all known workspaces, stored in ~/.seekr/workspaces.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkspaceRegistry {
+    workspaces: Vec<WorkspaceEntry>,
+    #[serde(skip)]
+    registry_path: PathBuf,
+}
+
+impl WorkspaceRegistry {
+    /// Load the registry from disk or create an empty one
+    pub fn load() -> Result<Self> {
+        let registry_path = Workspace::seekr_home()?.join("workspaces.json");
+
+        if registry_path.exists() {
+            let content =
+                fs::read_to_string(&registry_path).context("Failed to read workspace registry")?;
+            let mut registry: WorkspaceRegistry =
+                serde_json::from_str(&content).context("Failed to parse workspace registry")?;
+            registry.registry_path = registry_path;
+            Ok(registry)
+        } else {
+            Ok(Self {
+                workspaces: Vec::new(),
+                registry_path,
+            })
+        }
+    }
+
+    /// Load, modify and save the registry while holding its lock, so
+    /// concurrent updates from other workspaces are not lost
+    pub fn update(modify: impl FnOnce(&mut Self)) -> Result<()> {
+        let _lock = lock::wait_for(&Workspace::seekr_home()?.join("workspaces.lock"))?;
+        let mut registry = Self::load()?;
+        modify(&mut registry);
+        registry.save()
+    }
+
+    /// All registered workspaces
+    pub fn entries(&self) -> &[WorkspaceEntry] {
+        &self.workspaces
+    }
+
+    /// Find the workspace with a root at `path` or its nearest ancestor
+    ///
+    /// A directory indexed as a workspace of its own wins over workspaces
+    /// that merely include it as an added root.
+    pub fn find_for(&self, path: &Path) -> Option<&WorkspaceEntry> {
+        path.ancestors().find_map(|ancestor| {
+            self.workspaces
+                .iter()
+                .find(|w| w.root == ancestor)
+                .or_else(|| {
+                    self.workspaces
+                        .iter()
+                        .find(|w| w.added_roots.iter().any(|r| r == ancestor))
+                })
+        })
+    }
+
+    /// Insert or replace an entry (matched by id)
+    pub fn upsert(&mut self, entry: WorkspaceEntry) {
+        match self.workspaces.iter_mut().find(|w| w.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => self.workspaces.push(entry),
+        }
+    }
+
+    /// Remove an entry (matched by id), returning whether it was present
+    pub fn remove(&mut self, id: &str) -> bool {
+        let before = self.workspaces.len();
+        self.workspaces.retain(|w| w.id != id);
+        self.workspaces.len() != before
+    }
+
+    /// Save the registry to disk (only through `update`)
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.registry_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&self)?;
+        write_atomic(&self.registry_path, content)
+    }
+}
+
+/// Derive a readable, stable id from a canonical root path
+///
+/// Uses the directory name for readability plus a 64-bit FNV-1a hash of the
+/// full path, so two checkouts named `app` never share an index.
+fn workspace_id(root: &Path) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in root.to_string_lossy().as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    format!("{}-{:016x}", sanitized_name(root), hash)
+}
+
+/// Directory name of a path, reduced to `[A-Za-z0-9_-]`
+fn sanitized_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "root".to_string())
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(label: &str, path: &str, main: bool) -> Root {
+        Root {
+            label: label.to_string(),
+            path: PathBuf::from(path),
+            main,
+            dependency: false,
+        }
+    }
+
+    #[test]
+    fn test_workspace_paths_of_nested_roots() {
+        let roots = vec![
+            root("svc", "/repo/svc", true),
+            root("sdk", "/repo/svc/vendor/sdk", false),
+            root("shared", "/repo/libs/shared", false),
+        ];
+        let path_of = |file: &str| {
+            let file = Path::new(file);
+            owning_root(&roots, file).map(|root| root.workspace_path(file))
+        };
+
+        assert_eq!(
+            path_of("/repo/svc/src/main.rs").as_deref(),
+            Some("src/main.rs")
+        );
+        assert_eq!(
+            path_of("/repo/svc/vendor/sdk/src/client.rs").as_deref(),
+            Some("@sdk/src/client.rs")
+        );
+        assert_eq!(
+            path_of("/repo/libs/shared/lib.rs").as_deref(),
+            Some("@shared/lib.rs")
+        );
+        assert_eq!(path_of("/repo/other/lib.rs"), None);
+    }
+}